@@ -0,0 +1,7 @@
+extern crate cc;
+
+fn main() {
+    // alsa-lib's error handler is variadic, which can't be implemented in Rust,
+    // so a small C shim formats the message before handing it over.
+    cc::Build::new().file("src/lib_error.c").compile("alsa_rs_lib_error");
+}
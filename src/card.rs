@@ -3,6 +3,9 @@ use libc::{c_int, c_char};
 use super::error::*;
 use alsa;
 use std::ffi::CStr;
+use ctl_int::{Ctl, CardInfo};
+use hctl::HCtl;
+use mixer::Mixer;
 
 /// An ALSA sound card, uniquely identified by its index.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -13,6 +16,9 @@ pub struct Iter(c_int);
 
 impl Iter {
     pub fn new() -> Iter { Iter(-1) }
+
+    /// Iterates over existing sound cards together with their card info.
+    pub fn all() -> InfoIter { InfoIter(Iter::new()) }
 }
 
 impl Iterator for Iter {
@@ -27,6 +33,17 @@ impl Iterator for Iter {
     }
 }
 
+/// Iterate over existing sound cards and their info, see `Iter::all`.
+pub struct InfoIter(Iter);
+
+impl Iterator for InfoIter {
+    type Item = Result<(Card, CardInfo)>;
+
+    fn next(&mut self) -> Option<Result<(Card, CardInfo)>> {
+        self.0.next().map(|c| c.and_then(|c| c.ctl().and_then(|ctl| ctl.card_info()).map(|i| (c, i))))
+    }
+}
+
 impl Card {
     pub fn new(index: c_int) -> Card { Card(index) }
     pub fn from_str(s: &CStr) -> Result<Card> {
@@ -44,6 +61,25 @@ impl Card {
     }
 
     pub fn get_index(&self) -> c_int { self.0 }
+
+    /// Returns the card's string id, e g, "PCH".
+    pub fn get_id(&self) -> Result<String> {
+        let c = try!(self.ctl());
+        let i = try!(c.card_info());
+        i.get_id().map(|s| s.to_string())
+    }
+
+    /// Opens the control interface of this card ("hw:X").
+    pub fn ctl(&self) -> Result<Ctl> { Ctl::from_card(self, false) }
+
+    /// Opens and loads the high level control interface of this card ("hw:X").
+    pub fn hctl(&self) -> Result<HCtl> {
+        let h = try!(HCtl::new(&format!("hw:{}", self.0), false));
+        h.load().map(|_| h)
+    }
+
+    /// Opens the (simple) mixer of this card ("hw:X").
+    pub fn mixer(&self) -> Result<Mixer> { Mixer::new(&format!("hw:{}", self.0), false) }
 }
 
 #[test]
@@ -52,3 +88,11 @@ fn print_cards() {
         println!("Card #{}: {} ({})", a.get_index(), a.get_name().unwrap(), a.get_longname().unwrap())
     }
 }
+
+#[test]
+fn print_card_infos() {
+    for (a, i) in Iter::all().map(|a| a.unwrap()) {
+        println!("Card #{} ({}): {} - {}, power {:?}", a.get_index(), a.get_id().unwrap(), i.get_driver().unwrap(),
+            i.get_mixername().unwrap(), a.ctl().unwrap().power_state());
+    }
+}
@@ -0,0 +1,309 @@
+//! Configuration tree API (snd_config_*)
+//!
+//! # Example
+//! Open a PCM from an in-memory configuration, without touching `~/.asoundrc`.
+//!
+//! ```no_run
+//! use alsa::config::Config;
+//! use alsa::{PCM, Direction};
+//! use std::ffi::CString;
+//!
+//! let c = Config::from_str("pcm.quiet { type softvol slave.pcm \"hw:0\" control.name \"Quiet\" }").unwrap();
+//! let pcm = PCM::open_lconf(&CString::new("quiet").unwrap(), Direction::Playback, false, &c).unwrap();
+//! ```
+
+use alsa;
+use libc::{c_char, c_int, c_long, c_longlong, c_double};
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::{ptr, fmt};
+use super::error::*;
+use super::io::{Input, Output, input_handle, output_handle};
+
+alsa_enum!(
+    /// [SND_CONFIG_TYPE_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___config.html) constants
+    ConfigType, ALL_CONFIG_TYPES[6],
+
+    Integer = SND_CONFIG_TYPE_INTEGER,
+    Integer64 = SND_CONFIG_TYPE_INTEGER64,
+    Real = SND_CONFIG_TYPE_REAL,
+    String = SND_CONFIG_TYPE_STRING,
+    Pointer = SND_CONFIG_TYPE_POINTER,
+    Compound = SND_CONFIG_TYPE_COMPOUND,
+);
+
+/// The value of a configuration node
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Integer(i64),
+    Integer64(i64),
+    Real(f64),
+    String(&'a str),
+    Pointer,
+    Compound,
+}
+
+/// An owned configuration tree (or a single node, not yet added to a tree) - see
+/// [snd_config_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___config.html)
+///
+/// The tree is deleted when this struct is dropped.
+pub struct Config(*mut alsa::snd_config_t);
+
+unsafe impl Send for Config {}
+
+impl Drop for Config {
+    fn drop(&mut self) { if self.0 != ptr::null_mut() { unsafe { alsa::snd_config_delete(self.0) }; }}
+}
+
+fn cstr(s: &str) -> CString { CString::new(s).unwrap() }
+
+impl Config {
+    /// Creates an empty top level node.
+    pub fn top() -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_top(&mut p)).map(|_| Config(p))
+    }
+
+    /// Parses a configuration from a string, e g, the contents of an asound.conf file.
+    pub fn from_str(s: &str) -> Result<Config> {
+        let c = try!(Config::top());
+        let i = try!(Input::buffer_open(s.as_bytes()));
+        c.load(&i).map(|_| c)
+    }
+
+    /// Parses a configuration file, e g, "/usr/share/alsa/alsa.conf".
+    pub fn from_file(path: &str) -> Result<Config> {
+        let c = try!(Config::top());
+        let i = try!(Input::file_open(path));
+        c.load(&i).map(|_| c)
+    }
+
+    /// Loads (more) configuration into this tree.
+    pub fn load(&self, i: &Input) -> Result<()> {
+        acheck!(snd_config_load(self.0, input_handle(i))).map(|_| ())
+    }
+
+    /// Loads configuration into this tree, with new nodes overriding existing ones.
+    pub fn load_override(&self, i: &Input) -> Result<()> {
+        acheck!(snd_config_load_override(self.0, input_handle(i))).map(|_| ())
+    }
+
+    pub fn new_integer(key: &str, v: i64) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_imake_integer(&mut p, cstr(key).as_ptr(), v as c_long)).map(|_| Config(p))
+    }
+
+    pub fn new_integer64(key: &str, v: i64) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_imake_integer64(&mut p, cstr(key).as_ptr(), v as c_longlong)).map(|_| Config(p))
+    }
+
+    pub fn new_real(key: &str, v: f64) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_imake_real(&mut p, cstr(key).as_ptr(), v as c_double)).map(|_| Config(p))
+    }
+
+    pub fn new_string(key: &str, v: &str) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_imake_string(&mut p, cstr(key).as_ptr(), cstr(v).as_ptr())).map(|_| Config(p))
+    }
+
+    /// Creates an empty compound node. If join is true, the node's id is split at dots when saved.
+    pub fn new_compound(key: &str, join: bool) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_make_compound(&mut p, cstr(key).as_ptr(), if join { 1 } else { 0 })).map(|_| Config(p))
+    }
+
+    /// Returns the root node of this tree.
+    pub fn node<'a>(&'a self) -> Node<'a> { Node(self.0, PhantomData) }
+
+    /// Wrapper around `self.node().search(key)`
+    pub fn search<'a>(&'a self, key: &str) -> Result<Node<'a>> { self.node().search(key) }
+
+    /// Wrapper around `self.node().iter()`
+    pub fn iter<'a>(&'a self) -> Iter<'a> { self.node().iter() }
+
+    /// Wrapper around `self.node().add(child)`
+    pub fn add<'a>(&'a self, child: Config) -> Result<Node<'a>> { self.node().add(child) }
+
+    /// Removes a node (and its children) from the tree, e g, "pcm.test.ttl".
+    ///
+    /// Takes `&mut self` so that no `Node` referring to the removed node can outlive it.
+    pub fn delete(&mut self, key: &str) -> Result<()> {
+        let n = try!(self.search(key)).0;
+        if n == self.0 { return Err(Error::new("snd_config_delete", ::libc::EINVAL)) }
+        acheck!(snd_config_delete(n)).map(|_| ())
+    }
+
+    /// Wrapper around `self.node().save(o)`
+    pub fn save(&self, o: &mut Output) -> Result<()> { self.node().save(o) }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.node().fmt(f) }
+}
+
+/// Returns the raw snd_config_t pointer, for use with alsa-sys functions not wrapped here.
+pub(crate) fn config_ptr(c: &Config) -> *mut alsa::snd_config_t { c.0 }
+
+/// A node inside a `Config` tree
+pub struct Node<'a>(*mut alsa::snd_config_t, PhantomData<&'a Config>);
+
+impl<'a> Node<'a> {
+    pub fn get_type(&self) -> ConfigType {
+        ConfigType::from_c_int(unsafe { alsa::snd_config_get_type(self.0) } as c_int, "snd_config_get_type").unwrap()
+    }
+
+    pub fn get_id(&self) -> Result<&'a str> {
+        let mut p = ptr::null();
+        try!(acheck!(snd_config_get_id(self.0, &mut p)));
+        from_const("snd_config_get_id", p)
+    }
+
+    pub fn set_id(&self, id: &str) -> Result<()> {
+        acheck!(snd_config_set_id(self.0, cstr(id).as_ptr())).map(|_| ())
+    }
+
+    pub fn get_integer(&self) -> Result<i64> {
+        let mut v = 0;
+        acheck!(snd_config_get_integer(self.0, &mut v)).map(|_| v as i64)
+    }
+
+    pub fn get_integer64(&self) -> Result<i64> {
+        let mut v = 0;
+        acheck!(snd_config_get_integer64(self.0, &mut v)).map(|_| v as i64)
+    }
+
+    pub fn get_real(&self) -> Result<f64> {
+        let mut v = 0.0;
+        acheck!(snd_config_get_real(self.0, &mut v)).map(|_| v as f64)
+    }
+
+    pub fn get_string(&self) -> Result<&'a str> {
+        let mut p = ptr::null();
+        try!(acheck!(snd_config_get_string(self.0, &mut p)));
+        from_const("snd_config_get_string", p)
+    }
+
+    /// Returns the value of any non-compound node as a string.
+    pub fn get_ascii(&self) -> Result<String> {
+        let mut p: *mut c_char = ptr::null_mut();
+        try!(acheck!(snd_config_get_ascii(self.0, &mut p)));
+        from_alloc("snd_config_get_ascii", p)
+    }
+
+    /// Interprets the value as a boolean, e g, "yes", "off" or 1.
+    pub fn get_bool(&self) -> Result<bool> {
+        acheck!(snd_config_get_bool(self.0)).map(|v| v != 0)
+    }
+
+    pub fn get_value(&self) -> Result<Value<'a>> {
+        Ok(match self.get_type() {
+            ConfigType::Integer => Value::Integer(try!(self.get_integer())),
+            ConfigType::Integer64 => Value::Integer64(try!(self.get_integer64())),
+            ConfigType::Real => Value::Real(try!(self.get_real())),
+            ConfigType::String => Value::String(try!(self.get_string())),
+            ConfigType::Pointer => Value::Pointer,
+            ConfigType::Compound => Value::Compound,
+        })
+    }
+
+    pub fn set_integer(&self, v: i64) -> Result<()> { acheck!(snd_config_set_integer(self.0, v as c_long)).map(|_| ()) }
+    pub fn set_integer64(&self, v: i64) -> Result<()> { acheck!(snd_config_set_integer64(self.0, v as c_longlong)).map(|_| ()) }
+    pub fn set_real(&self, v: f64) -> Result<()> { acheck!(snd_config_set_real(self.0, v as c_double)).map(|_| ()) }
+    pub fn set_string(&self, v: &str) -> Result<()> { acheck!(snd_config_set_string(self.0, cstr(v).as_ptr())).map(|_| ()) }
+
+    /// Sets the value from a string, parsed according to the node's type.
+    pub fn set_ascii(&self, v: &str) -> Result<()> { acheck!(snd_config_set_ascii(self.0, cstr(v).as_ptr())).map(|_| ()) }
+
+    /// Finds a node, using dots to separate levels, e g, "pcm.default.type".
+    pub fn search(&self, key: &str) -> Result<Node<'a>> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_search(self.0, cstr(key).as_ptr(), &mut p)).map(|_| Node(p, PhantomData))
+    }
+
+    /// Iterates over the children of a compound node. Other nodes have no children.
+    pub fn iter(&self) -> Iter<'a> {
+        if self.get_type() != ConfigType::Compound { return Iter { cur: ptr::null_mut(), node: ptr::null_mut(), _p: PhantomData } }
+        Iter { cur: unsafe { alsa::snd_config_iterator_first(self.0) }, node: self.0, _p: PhantomData }
+    }
+
+    /// Adds a child node to this compound node, and returns it.
+    pub fn add(&self, child: Config) -> Result<Node<'a>> {
+        try!(acheck!(snd_config_add(self.0, child.0)));
+        let mut child = child;
+        let p = child.0;
+        child.0 = ptr::null_mut();
+        Ok(Node(p, PhantomData))
+    }
+
+    /// Makes a deep copy of this node, detached from the tree.
+    pub fn copy(&self) -> Result<Config> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_config_copy(&mut p, self.0)).map(|_| Config(p))
+    }
+
+    /// Writes this node and its children, in the same syntax as asound.conf.
+    pub fn save(&self, o: &mut Output) -> Result<()> {
+        acheck!(snd_config_save(self.0, output_handle(o))).map(|_| ())
+    }
+}
+
+impl<'a> fmt::Debug for Node<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Node({:?}, {:?})", self.get_id(), self.get_value())
+    }
+}
+
+/// Iterator over the children of a compound node
+pub struct Iter<'a> {
+    cur: alsa::snd_config_iterator_t,
+    node: *mut alsa::snd_config_t,
+    _p: PhantomData<&'a Config>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Node<'a>;
+    fn next(&mut self) -> Option<Node<'a>> {
+        if self.node.is_null() || self.cur == unsafe { alsa::snd_config_iterator_end(self.node) } { return None }
+        let n = unsafe { alsa::snd_config_iterator_entry(self.cur) };
+        self.cur = unsafe { alsa::snd_config_iterator_next(self.cur) };
+        Some(Node(n, PhantomData))
+    }
+}
+
+#[test]
+fn config_load_and_build() {
+    let mut c = Config::from_str(r#"
+        pcm.test {
+            type plug
+            slave.pcm "hw:0"
+            ttl 5
+            enabled yes
+        }
+    "#).unwrap();
+    {
+        let t = c.search("pcm.test").unwrap();
+        assert_eq!(t.get_type(), ConfigType::Compound);
+        assert_eq!(t.search("type").unwrap().get_string().unwrap(), "plug");
+        assert_eq!(c.search("pcm.test.slave.pcm").unwrap().get_value().unwrap(), Value::String("hw:0"));
+        assert_eq!(t.search("ttl").unwrap().get_integer().unwrap(), 5);
+        assert!(t.search("enabled").unwrap().get_bool().unwrap());
+        assert!(t.search("nonexistent").is_err());
+        let ids: Vec<_> = t.iter().map(|n| n.get_id().unwrap().to_string()).collect();
+        assert_eq!(ids, vec!("type", "slave", "ttl", "enabled"));
+        assert_eq!(t.search("ttl").unwrap().iter().count(), 0);
+
+        let n = t.add(Config::new_real("gain", 0.5).unwrap()).unwrap();
+        assert_eq!(n.get_real().unwrap(), 0.5);
+    }
+    c.delete("pcm.test.ttl").unwrap();
+    let mut o = Output::buffer_open().unwrap();
+    c.save(&mut o).unwrap();
+    let s = format!("{}", o);
+    assert!(s.contains("gain 0.5"));
+    assert!(!s.contains("ttl"));
+
+    let c2 = Config::from_str(&s).unwrap();
+    assert_eq!(c2.search("pcm.test.gain").unwrap().get_real().unwrap(), 0.5);
+}
@@ -4,9 +4,13 @@ use std::ffi::{CStr, CString};
 use super::error::*;
 use super::mixer::MilliBel;
 use super::Round;
+use super::tlv::{Tlv, DbScale};
+use super::pcm::HwParams;
 use std::{ptr, mem, fmt, cmp};
 use {Card, poll};
 use std::cell::UnsafeCell;
+use futures::{Stream, Async};
+use futures::task::Context;
 use libc::{c_uint, c_void, size_t, c_long, c_int, pollfd, c_short};
 
 /// We prefer not to allocate for every ElemId, ElemInfo or ElemValue.
@@ -17,6 +21,11 @@ const ELEM_ID_SIZE: usize = 64;
 // const ELEM_VALUE_SIZE: usize = 1224;
 // const ELEM_INFO_SIZE: usize = 272;
 
+// Not in alsa-sys
+extern "C" {
+    fn snd_ctl_elem_add_bytes(ctl: *mut alsa::snd_ctl_t, id: *const alsa::snd_ctl_elem_id_t, count: c_uint) -> c_int;
+}
+
 /// [snd_ctl_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) wrapper
 pub struct Ctl(*mut alsa::snd_ctl_t);
 
@@ -43,6 +52,16 @@ impl Ctl {
     pub fn card_info(&self) -> Result<CardInfo> { CardInfo::new().and_then(|c|
         acheck!(snd_ctl_card_info(self.0, c.0)).map(|_| c)) }
 
+    pub fn power_state(&self) -> Result<PowerState> {
+        let mut p: c_uint = 0;
+        try!(acheck!(snd_ctl_get_power_state(self.0, &mut p)));
+        PowerState::from_c_uint(p)
+    }
+
+    pub fn set_power_state(&self, p: PowerState) -> Result<()> {
+        acheck!(snd_ctl_set_power_state(self.0, p as c_uint)).map(|_| ())
+    }
+
     pub fn wait(&self, timeout_ms: Option<u32>) -> Result<bool> {
         acheck!(snd_ctl_wait(self.0, timeout_ms.map(|x| x as c_int).unwrap_or(-1))).map(|i| i == 1) }
 
@@ -76,6 +95,113 @@ impl Ctl {
         let e = try!(event_new());
         acheck!(snd_ctl_read(self.0, e.0)).map(|r| if r == 1 { Some(e) } else { None })
     }
+
+    /// Subscribes to events and returns a stream of them.
+    pub fn event_stream<'a>(&'a self) -> Result<EventStream<'a>> {
+        try!(self.subscribe_events(true));
+        Ok(EventStream(self, poll::ReadyWaker::new()))
+    }
+
+    /// Lists the ids of all elements of the card, without loading them as `HCtl` does.
+    pub fn elem_list(&self) -> Result<ElemList> {
+        let list = try!(elem_list_new());
+        try!(acheck!(snd_ctl_elem_list(self.0, list.0)));
+        try!(acheck!(snd_ctl_elem_list_alloc_space(list.0, list.get_count())));
+        acheck!(snd_ctl_elem_list(self.0, list.0)).map(|_| list)
+    }
+
+    pub fn elem_info(&self, id: &ElemId) -> Result<ElemInfo> {
+        let info = try!(elem_info_new());
+        unsafe { alsa::snd_ctl_elem_info_set_id(info.0, elem_id_ptr(id)) };
+        acheck!(snd_ctl_elem_info(self.0, info.0)).map(|_| info)
+    }
+
+    /// Returns the names of all items of an enumerated element.
+    pub fn elem_item_names(&self, info: &ElemInfo) -> Result<Vec<String>> {
+        elem_info_item_names(info, |i| acheck!(snd_ctl_elem_info(self.0, i.0)).map(|_| ()))
+    }
+
+    /// Reads the value of the element given by the id of `val` (see `ElemValue::set_id`).
+    pub fn elem_read(&self, val: &mut ElemValue) -> Result<()> {
+        acheck!(snd_ctl_elem_read(self.0, elem_value_ptr(val))).map(|_| ())
+    }
+
+    /// Writes the value of the element given by the id of `val`.
+    ///
+    /// Returns true if the value was changed.
+    pub fn elem_write(&self, val: &ElemValue) -> Result<bool> {
+        acheck!(snd_ctl_elem_write(self.0, elem_value_ptr(val))).map(|e| e > 0)
+    }
+
+    /// Reads and decodes the TLV data of an element, e g, its dB scale.
+    pub fn elem_tlv_read(&self, id: &ElemId) -> Result<Tlv> {
+        let mut v = vec![0 as c_uint; TLV_BUF_SIZE];
+        try!(acheck!(snd_ctl_elem_tlv_read(self.0, elem_id_ptr(id), v.as_mut_ptr(), (v.len() * 4) as c_uint)));
+        tlv_from_buf("snd_ctl_elem_tlv_read", &v)
+    }
+
+    pub fn elem_tlv_write(&self, id: &ElemId, tlv: &Tlv) -> Result<()> {
+        let v = tlv.to_raw();
+        acheck!(snd_ctl_elem_tlv_write(self.0, elem_id_ptr(id), v.as_ptr())).map(|_| ())
+    }
+
+    pub fn elem_tlv_command(&self, id: &ElemId, tlv: &Tlv) -> Result<()> {
+        let v = tlv.to_raw();
+        acheck!(snd_ctl_elem_tlv_command(self.0, elem_id_ptr(id), v.as_ptr())).map(|_| ())
+    }
+
+    fn user_elem<'a>(&'a self, id: &ElemId, db_range: Option<(MilliBel, MilliBel)>) -> Result<UserElem<'a>> {
+        let e = UserElem(self, Some(id.clone()));
+        if let Some((min, max)) = db_range {
+            try!(self.elem_tlv_write(id, &Tlv::Db(DbScale::MinMax { min: min, max: max })));
+        }
+        Ok(e)
+    }
+
+    /// Adds a user defined integer element to the card, optionally with a dB range (for the min and max values).
+    ///
+    /// The element is removed when the returned `UserElem` is dropped, unless `UserElem::keep` is called.
+    pub fn add_integer_elem<'a>(&'a self, id: &ElemId, count: u32, min: i32, max: i32, step: i32,
+        db_range: Option<(MilliBel, MilliBel)>) -> Result<UserElem<'a>> {
+        try!(acheck!(snd_ctl_elem_add_integer(self.0, elem_id_ptr(id), count as c_uint, min as c_long, max as c_long, step as c_long)));
+        self.user_elem(id, db_range)
+    }
+
+    pub fn add_integer64_elem<'a>(&'a self, id: &ElemId, count: u32, min: i64, max: i64, step: i64,
+        db_range: Option<(MilliBel, MilliBel)>) -> Result<UserElem<'a>> {
+        try!(acheck!(snd_ctl_elem_add_integer64(self.0, elem_id_ptr(id), count as c_uint, min, max, step)));
+        self.user_elem(id, db_range)
+    }
+
+    pub fn add_boolean_elem<'a>(&'a self, id: &ElemId, count: u32) -> Result<UserElem<'a>> {
+        try!(acheck!(snd_ctl_elem_add_boolean(self.0, elem_id_ptr(id), count as c_uint)));
+        self.user_elem(id, None)
+    }
+
+    pub fn add_enumerated_elem<'a>(&'a self, id: &ElemId, count: u32, names: &[&str]) -> Result<UserElem<'a>> {
+        let names: Vec<CString> = names.iter().map(|n| CString::new(*n).unwrap()).collect();
+        let ptrs: Vec<_> = names.iter().map(|n| n.as_ptr()).collect();
+        try!(acheck!(snd_ctl_elem_add_enumerated(self.0, elem_id_ptr(id), count as c_uint, ptrs.len() as c_uint, ptrs.as_ptr())));
+        self.user_elem(id, None)
+    }
+
+    pub fn add_bytes_elem<'a>(&'a self, id: &ElemId, count: u32) -> Result<UserElem<'a>> {
+        try!(from_code("snd_ctl_elem_add_bytes", unsafe { snd_ctl_elem_add_bytes(self.0, elem_id_ptr(id), count as c_uint) }));
+        self.user_elem(id, None)
+    }
+
+    /// Removes a user defined element from the card.
+    pub fn remove_elem(&self, id: &ElemId) -> Result<()> {
+        acheck!(snd_ctl_elem_remove(self.0, elem_id_ptr(id))).map(|_| ())
+    }
+
+    pub fn elem_lock(&self, id: &ElemId) -> Result<()> {
+        acheck!(snd_ctl_elem_lock(self.0, elem_id_ptr(id))).map(|_| ())
+    }
+
+    pub fn elem_unlock(&self, id: &ElemId) -> Result<()> {
+        acheck!(snd_ctl_elem_unlock(self.0, elem_id_ptr(id))).map(|_| ())
+    }
 }
 
 impl Drop for Ctl {
@@ -100,6 +226,57 @@ impl poll::PollDescriptors for Ctl {
 
 pub fn ctl_ptr(a: &Ctl) -> *mut alsa::snd_ctl_t { a.0 }
 
+/// Size of the buffer (in u32s) that TLV data is read into
+pub const TLV_BUF_SIZE: usize = 1024;
+
+/// Decodes TLV data read into a buffer, ignoring whatever comes after it
+pub fn tlv_from_buf(func: &'static str, v: &[c_uint]) -> Result<Tlv> {
+    let len = 2 + (v[1] as usize + 3) / 4;
+    if len > v.len() { return Err(Error::new(func, ::libc::ENOSPC)) }
+    Tlv::from_raw(&v[..len])
+}
+
+/// A user defined element, added by one of the `Ctl::add_*_elem` functions.
+///
+/// The element is removed from the card when this struct is dropped.
+pub struct UserElem<'a>(&'a Ctl, Option<ElemId>);
+
+impl<'a> UserElem<'a> {
+    pub fn get_id(&self) -> &ElemId { self.1.as_ref().unwrap() }
+
+    /// Leaves the element on the card (e g, for other applications to use) after this struct goes away.
+    pub fn keep(mut self) -> ElemId { self.1.take().unwrap() }
+}
+
+impl<'a> Drop for UserElem<'a> {
+    fn drop(&mut self) {
+        if let Some(ref id) = self.1 { let _ = self.0.remove_elem(id); }
+    }
+}
+
+/// [SND_CTL_POWER_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) constants
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PowerState {
+    /// Full on
+    D0 = 0x0000,
+    /// Partial on
+    D1 = 0x0100,
+    /// Partial on
+    D2 = 0x0200,
+    /// Off, with power
+    D3hot = 0x0300,
+    /// Off, without power
+    D3cold = 0x0301,
+}
+
+impl PowerState {
+    fn from_c_uint(c: c_uint) -> Result<PowerState> {
+        use self::PowerState::*;
+        [D0, D1, D2, D3hot, D3cold].iter().find(|&&x| c == x as c_uint).map(|&x| x)
+            .ok_or_else(|| Error::unsupported("snd_ctl_get_power_state"))
+    }
+}
+
 /// [snd_ctl_card_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) wrapper
 pub struct CardInfo(*mut alsa::snd_ctl_card_info_t);
 
@@ -243,6 +420,47 @@ impl ElemValue {
         else { unsafe { alsa::snd_ctl_elem_set_bytes(self.ptr, val.as_ptr() as *mut c_void, val.len() as size_t) }; Some(()) }
     }
 
+    pub fn get_id(&self) -> ElemId {
+        let r = elem_id_new().unwrap();
+        unsafe { alsa::snd_ctl_elem_value_get_id(self.ptr, elem_id_ptr(&r)) };
+        r
+    }
+
+    /// Sets which element this value belongs to; needed before calling `Ctl::elem_read` or `Ctl::elem_write`.
+    pub fn set_id(&mut self, id: &ElemId) {
+        unsafe { alsa::snd_ctl_elem_value_set_id(self.ptr, elem_id_ptr(id)) }
+    }
+
+    /// Returns the IEC958 (S/PDIF) status, or None if this is not an IEC958 element.
+    pub fn get_iec958(&self) -> Option<Iec958> {
+        if self.etype != ElemType::IEC958 { None }
+        else {
+            let mut a: alsa::snd_aes_iec958_t = unsafe { mem::zeroed() };
+            unsafe { alsa::snd_ctl_elem_value_get_iec958(self.ptr, &mut a) };
+            Some(Iec958 { status: a.status, subcode: a.subcode, dig_subframe: a.dig_subframe })
+        }
+    }
+
+    pub fn set_iec958(&mut self, val: &Iec958) -> Option<()> {
+        if self.etype != ElemType::IEC958 { None }
+        else {
+            let a = alsa::snd_aes_iec958_t { status: val.status, subcode: val.subcode, pad: 0, dig_subframe: val.dig_subframe };
+            unsafe { alsa::snd_ctl_elem_value_set_iec958(self.ptr, &a) };
+            Some(())
+        }
+    }
+
+    /// Parses a value in amixer syntax, e g, `80%,on` or `-6dB`, and sets the id from info.
+    ///
+    /// Values are comma separated, one per channel; the last one is repeated for the remaining channels.
+    /// Integers can be given as raw values, percent of the range or dB (if the element has a dB scale).
+    /// Enumerated items can be given by name. The ctl is used to look up item names and dB scales.
+    pub fn parse_ascii(&mut self, ctl: &Ctl, info: &ElemInfo, s: &str) -> Result<()> {
+        if self.etype != info.get_type() { return Err(Error::unsupported("snd_ctl_ascii_value_parse")) }
+        let s = CString::new(s).unwrap();
+        acheck!(snd_ctl_ascii_value_parse(ctl.0, self.ptr, elem_info_ptr(info), s.as_ptr())).map(|_| ())
+    }
+
     /// Creates a new ElemValue.
     pub fn new(t: ElemType) -> Result<ElemValue> {
         // See max length in include/uapi/sound/asound.h in linux kernel for these values
@@ -273,12 +491,149 @@ impl fmt::Debug for ElemValue {
             Integer64 => write!(f, ",{:?}", self.get_integer64(a).unwrap()),
             Enumerated => write!(f, ",{:?}", self.get_enumerated(a).unwrap()),
             Bytes => write!(f, ",{:?}", self.get_byte(a).unwrap()),
+            IEC958 => write!(f, ",{:?}", self.get_iec958().unwrap()),
             _ => Ok(()),
         })};
         write!(f, ")")
     }
 }
 
+/// The value of an IEC958 (S/PDIF, HDMI) element - see [snd_aes_iec958_t](http://www.alsa-project.org/alsa-doc/alsa-lib/structsnd__aes__iec958.html)
+#[derive(Copy)]
+pub struct Iec958 {
+    /// AES/IEC958 channel status bits; see `Iec958Status` for setting the most common ones
+    pub status: [u8; 24],
+    pub subcode: [u8; 147],
+    pub dig_subframe: [u8; 4],
+}
+
+impl Clone for Iec958 {
+    fn clone(&self) -> Self { *self }
+}
+
+impl Default for Iec958 {
+    fn default() -> Self { Iec958 { status: [0; 24], subcode: [0; 147], dig_subframe: [0; 4] } }
+}
+
+impl fmt::Debug for Iec958 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Iec958 {{ status: {:?} }}", &self.status[..])
+    }
+}
+
+impl From<Iec958Status> for Iec958 {
+    fn from(s: Iec958Status) -> Iec958 { Iec958 { status: s.0, .. Default::default() } }
+}
+
+// Constants from asoundef.h, which are not in alsa-sys
+const IEC958_AES0_PROFESSIONAL: u8 = 1 << 0;
+const IEC958_AES0_NONAUDIO: u8 = 1 << 1;
+const IEC958_AES0_PRO_FS: u8 = 3 << 6;
+const IEC958_AES0_CON_NOT_COPYRIGHT: u8 = 1 << 2;
+const IEC958_AES1_CON_CATEGORY: u8 = 0x7f;
+const IEC958_AES1_CON_ORIGINAL: u8 = 1 << 7;
+const IEC958_AES2_PRO_SBITS: u8 = 7 << 0;
+const IEC958_AES2_PRO_WORDLEN: u8 = 7 << 3;
+const IEC958_AES3_CON_FS: u8 = 0x0f;
+const IEC958_AES4_CON_WORDLEN: u8 = 0x0f;
+
+/// Builder for the AES0-AES4 channel status bytes of an IEC958 stream.
+///
+/// Start with either `consumer` (S/PDIF, HDMI) or `professional` (AES/EBU), since several
+/// bits have different meanings in these two modes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Iec958Status([u8; 24]);
+
+impl Iec958Status {
+    /// Consumer mode, general category, no copyright, unknown rate and word length
+    pub fn consumer() -> Self {
+        let mut r = Iec958Status([0; 24]);
+        r.0[0] = IEC958_AES0_CON_NOT_COPYRIGHT;
+        r.0[3] = 1; // IEC958_AES3_CON_FS_NOTID
+        r
+    }
+
+    /// Professional mode, unknown rate and word length
+    pub fn professional() -> Self {
+        let mut r = Iec958Status([0; 24]);
+        r.0[0] = IEC958_AES0_PROFESSIONAL;
+        r
+    }
+
+    pub fn from_bytes(status: &[u8; 24]) -> Self { Iec958Status(*status) }
+    pub fn bytes(&self) -> &[u8; 24] { &self.0 }
+
+    pub fn is_professional(&self) -> bool { (self.0[0] & IEC958_AES0_PROFESSIONAL) != 0 }
+
+    /// Marks the stream as non-audio, e g, AC3 or DTS passthrough
+    pub fn non_audio(mut self, v: bool) -> Self {
+        if v { self.0[0] |= IEC958_AES0_NONAUDIO } else { self.0[0] &= !IEC958_AES0_NONAUDIO }
+        self
+    }
+
+    /// Copyright assertion (consumer mode only)
+    pub fn copyright(mut self, v: bool) -> Self {
+        if self.is_professional() { return self }
+        if v { self.0[0] &= !IEC958_AES0_CON_NOT_COPYRIGHT } else { self.0[0] |= IEC958_AES0_CON_NOT_COPYRIGHT }
+        self
+    }
+
+    /// Category code (IEC958_AES1_CON_xxx, consumer mode only) and whether this is an original (as opposed to a copy)
+    pub fn category(mut self, category: u8, original: bool) -> Self {
+        if self.is_professional() { return self }
+        self.0[1] = (category & IEC958_AES1_CON_CATEGORY) | if original { IEC958_AES1_CON_ORIGINAL } else { 0 };
+        self
+    }
+
+    /// Sample rate. Fails if the rate can not be represented in the current mode.
+    pub fn rate(mut self, rate: u32) -> Result<Self> {
+        if self.is_professional() {
+            let v = match rate {
+                0 => 0, 44100 => 1 << 6, 48000 => 2 << 6, 32000 => 3 << 6,
+                _ => return Err(Error::unsupported("Iec958Status::rate")),
+            };
+            self.0[0] = (self.0[0] & !IEC958_AES0_PRO_FS) | v;
+        } else {
+            let v = match rate {
+                0 => 1, 44100 => 0, 48000 => 2, 32000 => 3, 22050 => 4, 24000 => 6, 88200 => 8,
+                768000 => 9, 96000 => 0xa, 176400 => 0xc, 192000 => 0xe,
+                _ => return Err(Error::unsupported("Iec958Status::rate")),
+            };
+            self.0[3] = (self.0[3] & !IEC958_AES3_CON_FS) | v;
+        }
+        Ok(self)
+    }
+
+    /// Sample word length in bits. Fails if the length can not be represented in the current mode.
+    pub fn word_length(mut self, bits: u32) -> Result<Self> {
+        if self.is_professional() {
+            // (max 20 or 24 bits, word length field)
+            let v = match bits {
+                0 => 0, 16 => 2 | (4 << 3), 18 => 2 | (2 << 3), 20 => 2 | (5 << 3),
+                22 => 4 | (2 << 3), 24 => 4 | (5 << 3),
+                _ => return Err(Error::unsupported("Iec958Status::word_length")),
+            };
+            self.0[2] = (self.0[2] & !(IEC958_AES2_PRO_SBITS | IEC958_AES2_PRO_WORDLEN)) | v;
+        } else {
+            let v = match bits {
+                0 => 0, 16 => 0x2, 17 => 0xc, 18 => 0x4, 19 => 0x8, 20 => 0xa,
+                21 => 0xd, 22 => 0x5, 23 => 0x9, 24 => 0xb,
+                _ => return Err(Error::unsupported("Iec958Status::word_length")),
+            };
+            self.0[4] = (self.0[4] & !IEC958_AES4_CON_WORDLEN) | v;
+        }
+        Ok(self)
+    }
+
+    /// Sets rate and word length from the current rate and format of the hw params.
+    pub fn from_hw_params(self, hwp: &HwParams) -> Result<Self> {
+        let f = try!(hwp.get_format());
+        let bits = try!(acheck!(snd_pcm_format_width(f as c_int)));
+        let r = try!(hwp.get_rate());
+        self.rate(r).and_then(|s| s.word_length(bits as u32))
+    }
+}
+
 /// [snd_ctl_elem_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) wrapper
 pub struct ElemInfo(*mut alsa::snd_ctl_elem_info_t);
 
@@ -297,6 +652,109 @@ impl ElemInfo {
     pub fn get_type(&self) -> ElemType { ElemType::from_c_int(
         unsafe { alsa::snd_ctl_elem_info_get_type(self.0) } as c_int, "snd_ctl_elem_info_get_type").unwrap() }
     pub fn get_count(&self) -> u32 { unsafe { alsa::snd_ctl_elem_info_get_count(self.0) as u32 } }
+
+    pub fn get_id(&self) -> ElemId {
+        let r = elem_id_new().unwrap();
+        unsafe { alsa::snd_ctl_elem_info_get_id(self.0, elem_id_ptr(&r)) };
+        r
+    }
+
+    pub fn is_readable(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_readable(self.0) != 0 } }
+    pub fn is_writable(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_writable(self.0) != 0 } }
+    pub fn is_volatile(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_volatile(self.0) != 0 } }
+    pub fn is_inactive(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_inactive(self.0) != 0 } }
+    pub fn is_locked(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_locked(self.0) != 0 } }
+    pub fn is_tlv_readable(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_tlv_readable(self.0) != 0 } }
+    pub fn is_tlv_writable(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_tlv_writable(self.0) != 0 } }
+    pub fn is_tlv_commandable(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_tlv_commandable(self.0) != 0 } }
+    /// True if the element is locked by this application.
+    pub fn is_owner(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_owner(self.0) != 0 } }
+    /// True if the element was added by a user space application.
+    pub fn is_user(&self) -> bool { unsafe { alsa::snd_ctl_elem_info_is_user(self.0) != 0 } }
+
+    /// Returns the pid of the process that has locked the element, if any.
+    pub fn get_owner(&self) -> Option<i32> {
+        let r = unsafe { alsa::snd_ctl_elem_info_get_owner(self.0) };
+        if r < 0 { None } else { Some(r as i32) }
+    }
+
+    pub fn get_min(&self) -> Option<i32> {
+        if self.get_type() != ElemType::Integer { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_min(self.0) } as i32) }
+    }
+
+    pub fn get_max(&self) -> Option<i32> {
+        if self.get_type() != ElemType::Integer { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_max(self.0) } as i32) }
+    }
+
+    pub fn get_step(&self) -> Option<i32> {
+        if self.get_type() != ElemType::Integer { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_step(self.0) } as i32) }
+    }
+
+    pub fn get_min64(&self) -> Option<i64> {
+        if self.get_type() != ElemType::Integer64 { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_min64(self.0) } as i64) }
+    }
+
+    pub fn get_max64(&self) -> Option<i64> {
+        if self.get_type() != ElemType::Integer64 { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_max64(self.0) } as i64) }
+    }
+
+    pub fn get_step64(&self) -> Option<i64> {
+        if self.get_type() != ElemType::Integer64 { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_step64(self.0) } as i64) }
+    }
+
+    /// Number of items of an enumerated element.
+    ///
+    /// Use `Ctl::elem_item_names` or `hctl::Elem::item_names` to get the names of the items.
+    pub fn get_items(&self) -> Option<u32> {
+        if self.get_type() != ElemType::Enumerated { None }
+        else { Some(unsafe { alsa::snd_ctl_elem_info_get_items(self.0) } as u32) }
+    }
+
+    /// Returns the dimensions of the element's values, or an empty vector if the values are one dimensional.
+    pub fn get_dimensions(&self) -> Result<Vec<u32>> {
+        let n = try!(from_code("snd_ctl_elem_info_get_dimensions", unsafe { alsa::snd_ctl_elem_info_get_dimensions(self.0) }));
+        (0..n).map(|i| from_code("snd_ctl_elem_info_get_dimension",
+            unsafe { alsa::snd_ctl_elem_info_get_dimension(self.0, i as c_uint) }).map(|d| d as u32)).collect()
+    }
+}
+
+/// alsa-lib only returns the name of one enumerated item at a time, and the info has to be
+/// re-queried for each item. `query` should perform this re-query on the supplied info.
+pub fn elem_info_item_names<F: FnMut(&ElemInfo) -> Result<()>>(info: &ElemInfo, mut query: F) -> Result<Vec<String>> {
+    let items = match info.get_items() { Some(n) => n, None => return Ok(vec!()) };
+    let tmp = try!(elem_info_new());
+    unsafe { alsa::snd_ctl_elem_info_copy(tmp.0, info.0) };
+    (0..items).map(|i| {
+        unsafe { alsa::snd_ctl_elem_info_set_item(tmp.0, i as c_uint) };
+        try!(query(&tmp));
+        from_const("snd_ctl_elem_info_get_item_name", unsafe { alsa::snd_ctl_elem_info_get_item_name(tmp.0) })
+            .map(|s| s.to_string())
+    }).collect()
+}
+
+impl fmt::Debug for ElemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "ElemInfo({:?} x {}", self.get_type(), self.get_count()));
+        if let (Some(min), Some(max), Some(step)) = (self.get_min(), self.get_max(), self.get_step()) {
+            try!(write!(f, ", range {}..{} step {}", min, max, step));
+        }
+        if let (Some(min), Some(max), Some(step)) = (self.get_min64(), self.get_max64(), self.get_step64()) {
+            try!(write!(f, ", range {}..{} step {}", min, max, step));
+        }
+        if let Some(items) = self.get_items() { try!(write!(f, ", {} items", items)); }
+        try!(write!(f, ", access {}{}", if self.is_readable() { "r" } else { "-" }, if self.is_writable() { "w" } else { "-" }));
+        if self.is_volatile() { try!(write!(f, ", volatile")) };
+        if self.is_inactive() { try!(write!(f, ", inactive")) };
+        if self.is_locked() { try!(write!(f, ", locked")) };
+        if self.is_user() { try!(write!(f, ", user")) };
+        write!(f, ")")
+    }
 }
 
 //
@@ -369,6 +827,20 @@ impl ElemId {
         r.set_interface(iface);
         r
     }
+
+    /// Parses an element id in amixer syntax, e g, `numid=3,iface=MIXER,name='Master Playback Volume'`.
+    ///
+    /// The interface defaults to `ElemIface::Mixer` if not given.
+    pub fn from_ascii(s: &str) -> Result<ElemId> {
+        let r = ElemId::new(ElemIface::Mixer);
+        let s = CString::new(s).unwrap();
+        acheck!(snd_ctl_ascii_elem_id_parse(elem_id_ptr(&r), s.as_ptr())).map(|_| r)
+    }
+
+    /// Returns the element id in amixer syntax.
+    pub fn to_ascii(&self) -> Result<String> {
+        from_alloc("snd_ctl_ascii_elem_id_get", unsafe { alsa::snd_ctl_ascii_elem_id_get(elem_id_ptr(&self)) })
+    }
 }
 
 impl cmp::Eq for ElemId {}
@@ -395,6 +867,50 @@ impl fmt::Debug for ElemId {
     }
 }
 
+/// [snd_ctl_elem_list_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) wrapper
+pub struct ElemList(*mut alsa::snd_ctl_elem_list_t);
+
+impl Drop for ElemList {
+    fn drop(&mut self) {
+        unsafe { alsa::snd_ctl_elem_list_free_space(self.0) };
+        unsafe { alsa::snd_ctl_elem_list_free(self.0) };
+    }
+}
+
+fn elem_list_new() -> Result<ElemList> {
+    let mut p = ptr::null_mut();
+    acheck!(snd_ctl_elem_list_malloc(&mut p)).map(|_| ElemList(p))
+}
+
+impl ElemList {
+    /// Total number of elements on the card.
+    pub fn get_count(&self) -> u32 { unsafe { alsa::snd_ctl_elem_list_get_count(self.0) as u32 }}
+
+    /// Number of element ids actually contained in this list.
+    pub fn get_used(&self) -> u32 { unsafe { alsa::snd_ctl_elem_list_get_used(self.0) as u32 }}
+
+    pub fn get_id(&self, index: u32) -> Option<ElemId> {
+        if index >= self.get_used() { return None; }
+        let r = elem_id_new().unwrap();
+        unsafe { alsa::snd_ctl_elem_list_get_id(self.0, index as c_uint, elem_id_ptr(&r)) };
+        Some(r)
+    }
+
+    pub fn iter<'a>(&'a self) -> ElemListIter<'a> { ElemListIter(self, 0) }
+}
+
+/// Iterates over the element ids of an `ElemList`
+pub struct ElemListIter<'a>(&'a ElemList, u32);
+
+impl<'a> Iterator for ElemListIter<'a> {
+    type Item = ElemId;
+    fn next(&mut self) -> Option<ElemId> {
+        let r = self.0.get_id(self.1);
+        if r.is_some() { self.1 += 1; }
+        r
+    }
+}
+
 /// [snd_ctl_event_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) wrapper
 pub struct Event(*mut alsa::snd_ctl_event_t);
 
@@ -417,6 +933,30 @@ impl Event {
 }
 
 
+/// An element change, as yielded by the `futures::Stream` implementations
+#[derive(Debug, Clone)]
+pub struct CtlEvent {
+    pub id: ElemId,
+    pub mask: EventMask,
+}
+
+/// A `futures::Stream` of element changes, see `Ctl::event_stream`
+pub struct EventStream<'a>(&'a Ctl, poll::ReadyWaker);
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = CtlEvent;
+    type Error = Error;
+
+    fn poll_next(&mut self, cx: &mut Context) -> Result<Async<Option<CtlEvent>>> {
+        loop {
+            if !try!(self.1.ready_or_wake(self.0, cx)) { return Ok(Async::Pending) }
+            if let Some(e) = try!(self.0.read()) {
+                return Ok(Async::Ready(Some(CtlEvent { id: e.get_id(), mask: e.get_mask() })))
+            }
+        }
+    }
+}
+
 /// [SND_CTL_EVENT_MASK_XXX](http://www.alsa-project.org/alsa-doc/alsa-lib/group___control.html) bitmask
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct EventMask(pub u32);
@@ -429,6 +969,63 @@ impl EventMask {
    pub fn tlv(&self) -> bool { return (!self.remove()) && (self.0 & (1 << 3) != 0); }
 }
 
+#[test]
+fn print_elem_list() {
+    for a in super::card::Iter::new().map(|x| x.unwrap()) {
+        let c = Ctl::from_card(&a, false).unwrap();
+        let l = c.elem_list().unwrap();
+        println!("Card {}: {} elements", a.get_name().unwrap(), l.get_count());
+        for id in l.iter() {
+            let info = c.elem_info(&id).unwrap();
+            println!("  {:?} {:?} {:?}", info, info.get_dimensions(), c.elem_item_names(&info).unwrap());
+            let mut v = ElemValue::new(info.get_type()).unwrap();
+            v.set_id(&id);
+            println!("  {:?} - {:?}", id, c.elem_read(&mut v).map(|_| v));
+            if info.is_tlv_readable() { println!("    TLV: {:?}", c.elem_tlv_read(&id)) }
+        }
+    }
+}
+
+#[test]
+fn add_user_elem() {
+    let c = Ctl::new("hw:0", false).unwrap();
+    let mut id = ElemId::new(ElemIface::Mixer);
+    id.set_name(&CString::new("Rust Test Volume").unwrap());
+    {
+        let e = c.add_integer_elem(&id, 2, 0, 100, 1, Some((MilliBel(-5000), MilliBel(0)))).unwrap();
+        let info = c.elem_info(e.get_id()).unwrap();
+        assert!(info.is_user());
+        assert_eq!(info.get_count(), 2);
+        assert_eq!(info.get_max(), Some(100));
+        assert_eq!(c.get_db_range(e.get_id()).unwrap(), (MilliBel(-5000), MilliBel(0)));
+    }
+    assert!(c.elem_info(&id).is_err());
+}
+
+#[test]
+fn ascii_elem_id() {
+    let id = ElemId::from_ascii("numid=3,iface=CARD,name='Headphone Jack',index=1").unwrap();
+    assert_eq!(id.get_numid(), 3);
+    assert_eq!(id.get_interface(), ElemIface::Card);
+    assert_eq!(id.get_name().unwrap(), "Headphone Jack");
+    assert_eq!(id.get_index(), 1);
+    let a = id.to_ascii().unwrap();
+    assert_eq!(ElemId::from_ascii(&a).unwrap().to_ascii().unwrap(), a);
+    assert_eq!(ElemId::from_ascii("name='Master Playback Volume'").unwrap().get_interface(), ElemIface::Mixer);
+}
+
+#[test]
+fn iec958_status() {
+    let s = Iec958Status::consumer().category(0x02, true).rate(48000).unwrap().word_length(24).unwrap();
+    assert_eq!(&s.bytes()[..5], &[0x04, 0x82, 0x00, 0x02, 0x0b]);
+    let s = s.non_audio(true).copyright(true);
+    assert_eq!(s.bytes()[0], 0x02);
+    let p = Iec958Status::professional().rate(44100).unwrap().word_length(20).unwrap();
+    assert_eq!(&p.bytes()[..3], &[0x41, 0x00, 0x2a]);
+    assert!(p.rate(96000).is_err());
+    assert_eq!(Iec958::from(s).status, *s.bytes());
+}
+
 #[test]
 fn print_sizeof() {
     let elemid = unsafe { alsa::snd_ctl_elem_id_sizeof() } as usize;
@@ -5,6 +5,8 @@ use std::{fmt, ptr, str};
 use std::ffi::CStr;
 use nix;
 use std::error::Error as StdError;
+use std::io;
+use alsa;
 
 /// ALSA error
 ///
@@ -43,7 +45,7 @@ pub fn from_alloc(func: &'static str, s: *mut c_char) -> Result<String> {
 }
 
 pub fn from_code(func: &'static str, r: c_int) -> Result<c_int> {
-    if r < 0 { Err(Error::new(func, r)) }
+    if r < 0 { Err(Error::new(func, -r)) }
     else { Ok(r) }
 }
 
@@ -65,6 +67,77 @@ impl Error {
 
     /// Underlying error
     pub fn nix_error(&self) -> nix::Error { self.1 }
+
+    pub fn kind(&self) -> ErrorKind {
+        use nix::Errno::*;
+        match self.1 {
+            nix::Error::Sys(e) => match e {
+                EPIPE => ErrorKind::Xrun,
+                ESTRPIPE => ErrorKind::Suspended,
+                ENODEV => ErrorKind::Disconnected,
+                EBUSY => ErrorKind::Busy,
+                EAGAIN => ErrorKind::WouldBlock,
+                EINTR => ErrorKind::Interrupted,
+                ENOENT => ErrorKind::NotFound,
+                EACCES | EPERM => ErrorKind::PermissionDenied,
+                EINVAL => ErrorKind::InvalidArgument,
+                ENOSYS | EOPNOTSUPP => ErrorKind::Unsupported,
+                _ => ErrorKind::Other,
+            },
+            nix::Error::UnsupportedOperation => ErrorKind::Unsupported,
+            nix::Error::InvalidUtf8 => ErrorKind::InvalidUtf8,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Buffer underrun or overrun - call `PCM::prepare` or `PCM::recover` to continue.
+    pub fn is_xrun(&self) -> bool { self.kind() == ErrorKind::Xrun }
+
+    /// The stream is suspended - call `PCM::resume` or `PCM::recover` to continue.
+    pub fn is_suspended(&self) -> bool { self.kind() == ErrorKind::Suspended }
+
+    /// The device is gone and should be closed.
+    pub fn is_disconnected(&self) -> bool { self.kind() == ErrorKind::Disconnected }
+
+    /// A non-blocking operation would have blocked - poll and try again.
+    pub fn would_block(&self) -> bool { self.kind() == ErrorKind::WouldBlock }
+
+    /// The error message, as given by `snd_strerror`, which also knows about alsa-lib specific codes.
+    pub fn message(&self) -> &str {
+        match self.1 {
+            nix::Error::Sys(e) => from_const("snd_strerror", unsafe { alsa::snd_strerror(e as c_int) }).unwrap_or("Unknown error"),
+            _ => self.1.description(),
+        }
+    }
+}
+
+/// A classification of `Error`, for the errors that applications typically need to handle
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Buffer underrun (playback) or overrun (capture), i e, EPIPE
+    Xrun,
+    /// The stream is suspended and needs to be resumed, i e, ESTRPIPE
+    Suspended,
+    /// The device was unplugged, i e, ENODEV
+    Disconnected,
+    /// The device is in use by another process, i e, EBUSY
+    Busy,
+    /// Non-blocking operation could not complete right now, i e, EAGAIN
+    WouldBlock,
+    /// Interrupted by a signal, i e, EINTR
+    Interrupted,
+    /// No such device, file or item, i e, ENOENT
+    NotFound,
+    /// EACCES or EPERM
+    PermissionDenied,
+    /// EINVAL
+    InvalidArgument,
+    /// The operation is not supported by the device or by this crate
+    Unsupported,
+    /// A string returned by ALSA could not be converted to UTF-8
+    InvalidUtf8,
+    /// Any other error
+    Other,
 }
 
 pub fn invalid_str(func: &'static str) -> Error { Error(func, nix::Error::InvalidUtf8) }
@@ -84,6 +157,16 @@ impl From<Error> for fmt::Error {
     fn from(_: Error) -> fmt::Error { fmt::Error }
 }
 
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e.errno() {
+            Some(x) => io::Error::from_raw_os_error(x as i32),
+            None if e.kind() == ErrorKind::InvalidUtf8 => io::Error::new(io::ErrorKind::InvalidData, e),
+            None => io::Error::new(io::ErrorKind::Other, e),
+        }
+    }
+}
+
 
 #[test]
 fn broken_pcm_name() {
@@ -92,3 +175,16 @@ fn broken_pcm_name() {
     assert_eq!(e.func(), "snd_pcm_open");
     assert_eq!(e.errno().unwrap(), nix::Errno::ENOENT);
 }
+
+#[test]
+fn error_kinds() {
+    let e = Error::new("snd_pcm_writei", nix::Errno::EPIPE as c_int);
+    assert!(e.is_xrun());
+    assert!(!e.would_block());
+    assert_eq!(from_code("snd_pcm_resume", -(nix::Errno::ESTRPIPE as c_int)).unwrap_err().kind(), ErrorKind::Suspended);
+    assert_eq!(Error::unsupported("foo").kind(), ErrorKind::Unsupported);
+    assert_eq!(e.message(), "Broken pipe");
+    let i: io::Error = Error::new("snd_rawmidi_read", nix::Errno::EAGAIN as c_int).into();
+    assert_eq!(i.kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(i.raw_os_error(), Some(nix::Errno::EAGAIN as i32));
+}
@@ -24,13 +24,20 @@
 //!     }
 //! }
 //! ```
+//!
+//! See `JackMonitor` for a higher level way of doing the same, including waiting for changes.
 
 use alsa;
 use std::ffi::{CStr, CString};
 use super::error::*;
 use std::ptr;
-use super::{ctl_int, poll};
-use libc::{c_short, c_uint, c_int, pollfd};
+use super::{ctl_int, poll, card, Card};
+use super::poll::PollDescriptors;
+use super::tlv::Tlv;
+use libc::{c_short, c_uint, c_int, c_void, pollfd};
+use std::collections::VecDeque;
+use futures::{Stream, Async};
+use futures::task::Context;
 
 
 /// [snd_hctl_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___h_control.html) wrapper
@@ -68,6 +75,79 @@ impl HCtl {
 
     pub fn wait(&self, timeout_ms: Option<u32>) -> Result<bool> {
         acheck!(snd_hctl_wait(self.0, timeout_ms.map(|x| x as c_int).unwrap_or(-1))).map(|i| i == 1) }
+
+    pub fn handle_events(&self) -> Result<u32> { acheck!(snd_hctl_handle_events(self.0)).map(|x| x as u32) }
+
+    /// Returns a stream of changes to elements: value and info changes, as well as added and removed elements.
+    ///
+    /// Call `load` before this function. This sets the HCtl and element callbacks, so only one stream
+    /// can be active for a HCtl at any one time; if there already is one, this fails with EBUSY.
+    /// Callbacks set directly through alsa-sys are replaced, and cleared when the stream is dropped.
+    pub fn event_stream<'a>(&'a self) -> Result<EventStream<'a>> {
+        if !unsafe { alsa::snd_hctl_get_callback_private(self.0) }.is_null() {
+            return Err(Error::new("snd_hctl_set_callback", ::libc::EBUSY))
+        }
+        let mut q: Box<EventQueue> = Box::new(VecDeque::new());
+        let p = &mut *q as *mut EventQueue as *mut c_void;
+        unsafe {
+            alsa::snd_hctl_set_callback_private(self.0, p);
+            alsa::snd_hctl_set_callback(self.0, Some(hctl_callback));
+        }
+        for e in self.elem_iter() { set_elem_callback(e.1, p) }
+        Ok(EventStream(self, q, poll::ReadyWaker::new()))
+    }
+}
+
+type EventQueue = VecDeque<ctl_int::CtlEvent>;
+
+fn set_elem_callback(e: *mut alsa::snd_hctl_elem_t, p: *mut c_void) {
+    unsafe {
+        alsa::snd_hctl_elem_set_callback_private(e, p);
+        alsa::snd_hctl_elem_set_callback(e, if p.is_null() { None } else { Some(elem_callback) });
+    }
+}
+
+extern "C" fn elem_callback(e: *mut alsa::snd_hctl_elem_t, mask: c_uint) -> c_int {
+    let q = unsafe { alsa::snd_hctl_elem_get_callback_private(e) } as *mut EventQueue;
+    if q.is_null() { return 0 }
+    if let Ok(id) = ctl_int::elem_id_new() {
+        unsafe { alsa::snd_hctl_elem_get_id(e, ctl_int::elem_id_ptr(&id)) };
+        unsafe { (*q).push_back(ctl_int::CtlEvent { id: id, mask: ctl_int::EventMask(mask as u32) }) };
+    }
+    0
+}
+
+extern "C" fn hctl_callback(h: *mut alsa::snd_hctl_t, mask: c_uint, e: *mut alsa::snd_hctl_elem_t) -> c_int {
+    let p = unsafe { alsa::snd_hctl_get_callback_private(h) };
+    if p.is_null() { return 0 }
+    set_elem_callback(e, p);
+    elem_callback(e, mask)
+}
+
+/// A `futures::Stream` of element changes, see `HCtl::event_stream`
+pub struct EventStream<'a>(&'a HCtl, Box<EventQueue>, poll::ReadyWaker);
+
+impl<'a> Drop for EventStream<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            alsa::snd_hctl_set_callback((self.0).0, None);
+            alsa::snd_hctl_set_callback_private((self.0).0, ptr::null_mut());
+        }
+        for e in self.0.elem_iter() { set_elem_callback(e.1, ptr::null_mut()) }
+    }
+}
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = ctl_int::CtlEvent;
+    type Error = Error;
+
+    fn poll_next(&mut self, cx: &mut Context) -> Result<Async<Option<ctl_int::CtlEvent>>> {
+        loop {
+            if let Some(e) = self.1.pop_front() { return Ok(Async::Ready(Some(e))) }
+            if !try!(self.2.ready_or_wake(self.0, cx)) { return Ok(Async::Pending) }
+            try!(self.0.handle_events());
+        }
+    }
 }
 
 impl poll::PollDescriptors for HCtl {
@@ -112,6 +192,12 @@ impl<'a> Elem<'a> {
         let v = try!(ctl_int::elem_info_new());
         acheck!(snd_hctl_elem_info(self.1, ctl_int::elem_info_ptr(&v))).map(|_| v)
     }
+    /// Returns the names of all items, if this is an enumerated element.
+    pub fn item_names(&self) -> Result<Vec<String>> {
+        let i = try!(self.info());
+        ctl_int::elem_info_item_names(&i, |i| acheck!(snd_hctl_elem_info(self.1, ctl_int::elem_info_ptr(i))).map(|_| ()))
+    }
+
     pub fn read(&self) -> Result<ctl_int::ElemValue> {
         let i = try!(self.info());
         let v = try!(ctl_int::elem_value_new(i.get_type(), i.get_count()));
@@ -121,6 +207,189 @@ impl<'a> Elem<'a> {
     pub fn write(&self, v: &ctl_int::ElemValue) -> Result<bool> {
         acheck!(snd_hctl_elem_write(self.1, ctl_int::elem_value_ptr(&v))).map(|e| e > 0)
     }
+
+    /// Reads and decodes the TLV data of this element, e g, its dB scale.
+    pub fn tlv_read(&self) -> Result<Tlv> {
+        let mut v = vec![0 as c_uint; ctl_int::TLV_BUF_SIZE];
+        try!(acheck!(snd_hctl_elem_tlv_read(self.1, v.as_mut_ptr(), (v.len() * 4) as c_uint)));
+        ctl_int::tlv_from_buf("snd_hctl_elem_tlv_read", &v)
+    }
+
+    pub fn tlv_write(&self, tlv: &Tlv) -> Result<()> {
+        let v = tlv.to_raw();
+        acheck!(snd_hctl_elem_tlv_write(self.1, v.as_ptr())).map(|_| ())
+    }
+
+    pub fn tlv_command(&self, tlv: &Tlv) -> Result<()> {
+        let v = tlv.to_raw();
+        acheck!(snd_hctl_elem_tlv_command(self.1, v.as_ptr())).map(|_| ())
+    }
+}
+
+/// The kind of a jack, as guessed from its name
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum JackKind {
+    Headphone,
+    /// Combined headphone and microphone jack
+    Headset,
+    Mic,
+    LineOut,
+    LineIn,
+    Speaker,
+    /// HDMI or DisplayPort
+    Hdmi,
+    Other,
+}
+
+/// The state of a jack detection control
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Jack {
+    /// Card index
+    pub card: i32,
+    /// The name of the control, without the " Jack" suffix (e g, "Front Headphone")
+    pub name: String,
+    pub kind: JackKind,
+    /// The PCM device this jack belongs to, if known (HDMI jacks are named e g "HDMI/DP,pcm=3 Jack")
+    pub pcm_device: Option<u32>,
+    /// Phantom jacks have no detection; they are always plugged in
+    pub phantom: bool,
+    pub plugged: bool,
+}
+
+impl Jack {
+    /// Parses a jack control name; returns None if this is not a jack control.
+    pub fn from_name(card: i32, name: &str, plugged: bool) -> Option<Jack> {
+        let (name, phantom) =
+            if name.ends_with(" Phantom Jack") { (&name[..name.len()-13], true) }
+            else if name.ends_with(" Jack") { (&name[..name.len()-5], false) }
+            else { return None };
+        let (name, dev) = match name.find(",pcm=") {
+            Some(i) => (&name[..i], name[i+5..].parse().ok()),
+            None => (name, None),
+        };
+        let kind =
+            if name.contains("HDMI") || name.contains("DP") { JackKind::Hdmi }
+            else if name.contains("Headset") || name.contains("Headphone Mic") { JackKind::Headset }
+            else if name.contains("Headphone") { JackKind::Headphone }
+            else if name.contains("Mic") { JackKind::Mic }
+            else if name.contains("Line Out") { JackKind::LineOut }
+            else if name.contains("Line") { JackKind::LineIn }
+            else if name.contains("Speaker") { JackKind::Speaker }
+            else { JackKind::Other };
+        Some(Jack { card: card, name: name.into(), kind: kind, pcm_device: dev, phantom: phantom, plugged: plugged || phantom })
+    }
+}
+
+/// Monitors the jack detection controls of one or all cards.
+///
+/// # Example
+/// ```no_run
+/// let mut m = ::alsa::hctl::JackMonitor::new(None).unwrap();
+/// for j in m.jacks() { println!("{:?}", j) }
+/// for j in m.changes() { println!("Changed: {:?}", j.unwrap()) }
+/// ```
+pub struct JackMonitor(Vec<(HCtl, Vec<(ctl_int::ElemId, Jack)>)>);
+
+impl JackMonitor {
+    /// Discovers the jacks of a card, or of all cards if `card` is `None`.
+    pub fn new(card: Option<&Card>) -> Result<JackMonitor> {
+        let mut v = vec!();
+        match card {
+            Some(c) => v.push(try!(JackMonitor::open(c))),
+            None => for c in card::Iter::new() { v.push(try!(JackMonitor::open(&try!(c)))) },
+        }
+        Ok(JackMonitor(v))
+    }
+
+    fn open(c: &Card) -> Result<(HCtl, Vec<(ctl_int::ElemId, Jack)>)> {
+        let h = try!(HCtl::open(&CString::new(format!("hw:{}", c.get_index())).unwrap(), true));
+        try!(h.load());
+        let mut jacks = vec!();
+        for e in h.elem_iter() {
+            let id = try!(e.get_id());
+            if id.get_interface() != ctl_int::ElemIface::Card { continue; }
+            let plugged = match try!(e.read()).get_boolean(0) { Some(p) => p, None => continue };
+            if let Some(j) = Jack::from_name(c.get_index(), try!(id.get_name()), plugged) { jacks.push((id, j)) }
+        }
+        Ok((h, jacks))
+    }
+
+    /// Returns all jacks, as of the latest call to `update`.
+    pub fn jacks(&self) -> Vec<Jack> {
+        self.0.iter().flat_map(|x| x.1.iter().map(|y| y.1.clone())).collect()
+    }
+
+    /// Reads pending events without blocking, and returns the jacks that were plugged in or unplugged.
+    pub fn update(&mut self) -> Result<Vec<Jack>> {
+        let mut r = vec!();
+        for &mut (ref h, ref mut jacks) in self.0.iter_mut() {
+            try!(h.handle_events());
+            for &mut (ref id, ref mut j) in jacks.iter_mut() {
+                if j.phantom { continue; }
+                let e = match h.find_elem(id) { Some(e) => e, None => continue };
+                let p = try!(e.read()).get_boolean(0).unwrap_or(false);
+                if p != j.plugged { j.plugged = p; r.push(j.clone()) }
+            }
+        }
+        Ok(r)
+    }
+
+    /// Waits for events (forever if timeout is `None`), then calls `update`.
+    pub fn wait(&mut self, timeout_ms: Option<u32>) -> Result<Vec<Jack>> {
+        let mut fds = try!(self.get());
+        try!(poll::poll(&mut fds, timeout_ms.map(|x| x as i32).unwrap_or(-1)));
+        self.update()
+    }
+
+    /// Returns a blocking iterator over jacks as they are plugged in or unplugged.
+    pub fn changes<'a>(&'a mut self) -> JackChanges<'a> { JackChanges(self, VecDeque::new()) }
+}
+
+impl poll::PollDescriptors for JackMonitor {
+    fn count(&self) -> usize { self.0.iter().map(|x| x.0.count()).sum() }
+    fn fill(&self, p: &mut [pollfd]) -> Result<usize> {
+        let mut n = 0;
+        for x in &self.0 { n += try!(x.0.fill(&mut p[n..])); }
+        Ok(n)
+    }
+    fn revents(&self, p: &[pollfd]) -> Result<poll::PollFlags> {
+        let (mut n, mut r) = (0, poll::PollFlags::empty());
+        for x in &self.0 {
+            let c = x.0.count();
+            r = r | try!(x.0.revents(&p[n..n+c]));
+            n += c;
+        }
+        Ok(r)
+    }
+}
+
+/// Iterator over jack changes, see `JackMonitor::changes`
+pub struct JackChanges<'a>(&'a mut JackMonitor, VecDeque<Jack>);
+
+impl<'a> Iterator for JackChanges<'a> {
+    type Item = Result<Jack>;
+    fn next(&mut self) -> Option<Result<Jack>> {
+        while self.1.is_empty() {
+            match self.0.wait(None) {
+                Ok(v) => self.1.extend(v),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.1.pop_front().map(Ok)
+    }
+}
+
+#[test]
+fn jack_names() {
+    let j = Jack::from_name(0, "HDMI/DP,pcm=3 Jack", true).unwrap();
+    assert_eq!((&*j.name, j.kind, j.pcm_device, j.phantom), ("HDMI/DP", JackKind::Hdmi, Some(3), false));
+    let j = Jack::from_name(1, "Internal Mic Phantom Jack", false).unwrap();
+    assert_eq!((&*j.name, j.kind, j.phantom, j.plugged), ("Internal Mic", JackKind::Mic, true, true));
+    assert_eq!(Jack::from_name(0, "Front Headphone Jack", false).unwrap().kind, JackKind::Headphone);
+    assert_eq!(Jack::from_name(0, "Headphone Mic Jack", false).unwrap().kind, JackKind::Headset);
+    assert_eq!(Jack::from_name(0, "Line Out Jack", false).unwrap().kind, JackKind::LineOut);
+    assert_eq!(Jack::from_name(0, "Line Jack", false).unwrap().kind, JackKind::LineIn);
+    assert!(Jack::from_name(0, "Master Playback Volume", false).is_none());
 }
 
 #[test]
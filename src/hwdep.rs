@@ -0,0 +1,216 @@
+//! Hardware dependent devices - firmware loading, DSP control and driver specific I/O
+
+use libc::{c_int, c_uint, c_void, size_t, c_short, pollfd};
+use super::ctl_int::{ctl_ptr, Ctl};
+use super::poll;
+use super::error::*;
+use alsa;
+use std::{ptr, io};
+use std::ffi::{CStr, CString};
+
+// Not in alsa-sys
+extern "C" {
+    fn snd_hwdep_poll_descriptors_count(hwdep: *mut alsa::snd_hwdep_t) -> c_int;
+}
+
+alsa_enum!(
+    /// [SND_HWDEP_IFACE_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___hw_dep.html) constants
+    Iface, ALL_IFACES[16],
+
+    Opl2 = SND_HWDEP_IFACE_OPL2,
+    Opl3 = SND_HWDEP_IFACE_OPL3,
+    Opl4 = SND_HWDEP_IFACE_OPL4,
+    SB16CSP = SND_HWDEP_IFACE_SB16CSP,
+    Emu10k1 = SND_HWDEP_IFACE_EMU10K1,
+    YSS225 = SND_HWDEP_IFACE_YSS225,
+    ICS2115 = SND_HWDEP_IFACE_ICS2115,
+    SScape = SND_HWDEP_IFACE_SSCAPE,
+    VX = SND_HWDEP_IFACE_VX,
+    Mixart = SND_HWDEP_IFACE_MIXART,
+    USX2Y = SND_HWDEP_IFACE_USX2Y,
+    EmuxWavetable = SND_HWDEP_IFACE_EMUX_WAVETABLE,
+    Bluetooth = SND_HWDEP_IFACE_BLUETOOTH,
+    USX2YPcm = SND_HWDEP_IFACE_USX2Y_PCM,
+    PCXHR = SND_HWDEP_IFACE_PCXHR,
+    SBRC = SND_HWDEP_IFACE_SB_RC,
+);
+
+/// Iterator over [Hwdep](http://www.alsa-project.org/alsa-doc/alsa-lib/group___hw_dep.html) devices of a card
+pub struct Iter<'a> {
+    ctl: &'a Ctl,
+    device: c_int,
+}
+
+impl<'a> Iter<'a> {
+    pub fn new(c: &'a Ctl) -> Iter<'a> { Iter { ctl: c, device: -1 }}
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Info>;
+    fn next(&mut self) -> Option<Result<Info>> {
+        match acheck!(snd_ctl_hwdep_next_device(ctl_ptr(&self.ctl), &mut self.device)) {
+            Err(e) => return Some(Err(e)),
+            Ok(_) if self.device == -1 => return None,
+            _ => {},
+        }
+        Some(Info::from_iter(&self.ctl, self.device))
+    }
+}
+
+/// [snd_hwdep_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___hw_dep.html) wrapper
+pub struct Info(*mut alsa::snd_hwdep_info_t);
+
+impl Drop for Info {
+    fn drop(&mut self) { unsafe { alsa::snd_hwdep_info_free(self.0) }; }
+}
+
+impl Info {
+    fn new() -> Result<Info> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_hwdep_info_malloc(&mut p)).map(|_| Info(p))
+    }
+
+    fn from_iter(c: &Ctl, device: i32) -> Result<Info> {
+        let r = try!(Info::new());
+        unsafe { alsa::snd_hwdep_info_set_device(r.0, device as c_uint) };
+        acheck!(snd_ctl_hwdep_info(ctl_ptr(&c), r.0)).map(|_| r)
+    }
+
+    pub fn get_device(&self) -> i32 { unsafe { alsa::snd_hwdep_info_get_device(self.0) as i32 }}
+    pub fn get_card(&self) -> i32 { unsafe { alsa::snd_hwdep_info_get_card(self.0) as i32 }}
+    pub fn get_id(&self) -> Result<&str> { from_const("snd_hwdep_info_get_id", unsafe { alsa::snd_hwdep_info_get_id(self.0) })}
+    pub fn get_name(&self) -> Result<&str> { from_const("snd_hwdep_info_get_name", unsafe { alsa::snd_hwdep_info_get_name(self.0) })}
+    pub fn get_iface(&self) -> Result<Iface> {
+        Iface::from_c_int(unsafe { alsa::snd_hwdep_info_get_iface(self.0) } as c_int, "snd_hwdep_info_get_iface")
+    }
+}
+
+/// [snd_hwdep_dsp_status_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___hw_dep.html) wrapper
+pub struct DspStatus(*mut alsa::snd_hwdep_dsp_status_t);
+
+impl Drop for DspStatus {
+    fn drop(&mut self) { unsafe { alsa::snd_hwdep_dsp_status_free(self.0) }; }
+}
+
+impl DspStatus {
+    fn new() -> Result<DspStatus> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_hwdep_dsp_status_malloc(&mut p)).map(|_| DspStatus(p))
+    }
+
+    pub fn get_version(&self) -> u32 { unsafe { alsa::snd_hwdep_dsp_status_get_version(self.0) as u32 }}
+    pub fn get_id(&self) -> Result<&str> { from_const("snd_hwdep_dsp_status_get_id", unsafe { alsa::snd_hwdep_dsp_status_get_id(self.0) })}
+    pub fn get_num_dsps(&self) -> u32 { unsafe { alsa::snd_hwdep_dsp_status_get_num_dsps(self.0) as u32 }}
+    /// Bitmask of loaded DSPs
+    pub fn get_dsp_loaded(&self) -> u32 { unsafe { alsa::snd_hwdep_dsp_status_get_dsp_loaded(self.0) as u32 }}
+    pub fn get_chip_ready(&self) -> bool { unsafe { alsa::snd_hwdep_dsp_status_get_chip_ready(self.0) != 0 }}
+}
+
+/// [snd_hwdep_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___hw_dep.html) wrapper
+pub struct Hwdep(*mut alsa::snd_hwdep_t);
+
+unsafe impl Send for Hwdep {}
+
+impl Drop for Hwdep {
+    fn drop(&mut self) { unsafe { alsa::snd_hwdep_close(self.0) }; }
+}
+
+impl Hwdep {
+
+    /// Wrapper around open that takes a &str instead of a &CStr
+    pub fn new(name: &str, nonblock: bool) -> Result<Self> {
+        Self::open(&CString::new(name).unwrap(), nonblock)
+    }
+
+    /// Opens the device for both reading and writing, e g, "hw:0,0".
+    pub fn open(name: &CStr, nonblock: bool) -> Result<Hwdep> {
+        let mut h = ptr::null_mut();
+        // FIXME: alsa::SND_HWDEP_OPEN_DUPLEX and SND_HWDEP_OPEN_NONBLOCK do not exist in alsa-sys
+        let flags = ::libc::O_RDWR | if nonblock { ::libc::O_NONBLOCK } else { 0 };
+        acheck!(snd_hwdep_open(&mut h, name.as_ptr(), flags)).map(|_| Hwdep(h))
+    }
+
+    pub fn info(&self) -> Result<Info> {
+        Info::new().and_then(|i| acheck!(snd_hwdep_info(self.0, i.0)).map(|_| i))
+    }
+
+    pub fn nonblock(&self, nonblock: bool) -> Result<()> {
+        acheck!(snd_hwdep_nonblock(self.0, if nonblock { 1 } else { 0 })).map(|_| ())
+    }
+
+    pub fn dsp_status(&self) -> Result<DspStatus> {
+        DspStatus::new().and_then(|s| acheck!(snd_hwdep_dsp_status(self.0, s.0)).map(|_| s))
+    }
+
+    /// Uploads a firmware image to DSP number `index`.
+    pub fn dsp_load(&self, index: u32, name: &str, image: &[u8]) -> Result<()> {
+        let mut p = ptr::null_mut();
+        try!(acheck!(snd_hwdep_dsp_image_malloc(&mut p)));
+        let n = CString::new(name).unwrap();
+        let r = unsafe {
+            alsa::snd_hwdep_dsp_image_set_index(p, index as c_uint);
+            alsa::snd_hwdep_dsp_image_set_name(p, n.as_ptr());
+            alsa::snd_hwdep_dsp_image_set_image(p, image.as_ptr() as *mut c_void);
+            alsa::snd_hwdep_dsp_image_set_length(p, image.len() as size_t);
+            let r = alsa::snd_hwdep_dsp_load(self.0, p);
+            alsa::snd_hwdep_dsp_image_free(p);
+            r
+        };
+        if r < 0 { Err(Error::new("snd_hwdep_dsp_load", -r as c_int)) } else { Ok(()) }
+    }
+
+    /// Driver specific ioctl. Returns the (non-negative) return value of the ioctl.
+    ///
+    /// This is unsafe because the kernel reads and writes `arg` according to `request`.
+    pub unsafe fn ioctl(&self, request: u32, arg: *mut c_void) -> Result<i32> {
+        acheck!(snd_hwdep_ioctl(self.0, request as c_uint, arg)).map(|r| r as i32)
+    }
+
+    pub fn io<'a>(&'a self) -> IO<'a> { IO(&self) }
+}
+
+impl poll::PollDescriptors for Hwdep {
+    fn count(&self) -> usize {
+        unsafe { snd_hwdep_poll_descriptors_count(self.0) as usize }
+    }
+    fn fill(&self, p: &mut [pollfd]) -> Result<usize> {
+        let z = unsafe { alsa::snd_hwdep_poll_descriptors(self.0, p.as_mut_ptr(), p.len() as c_uint) };
+        from_code("snd_hwdep_poll_descriptors", z).map(|_| z as usize)
+    }
+    fn revents(&self, p: &[pollfd]) -> Result<poll::PollFlags> {
+        let mut r = 0;
+        let z = unsafe { alsa::snd_hwdep_poll_descriptors_revents(self.0, p.as_ptr() as *mut pollfd, p.len() as c_uint, &mut r) };
+        from_code("snd_hwdep_poll_descriptors_revents", z).map(|_| poll::PollFlags::from_bits_truncate(r as c_short))
+    }
+}
+
+/// Implements `std::io::Read` and `std::io::Write` for `Hwdep`
+pub struct IO<'a>(&'a Hwdep);
+
+impl<'a> io::Read for IO<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let r = unsafe { alsa::snd_hwdep_read((self.0).0, buf.as_mut_ptr() as *mut c_void, buf.len() as size_t) };
+        if r < 0 { Err(io::Error::from_raw_os_error(-r as i32)) }
+        else { Ok(r as usize) }
+    }
+}
+
+impl<'a> io::Write for IO<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let r = unsafe { alsa::snd_hwdep_write((self.0).0, buf.as_ptr() as *const c_void, buf.len() as size_t) };
+        if r < 0 { Err(io::Error::from_raw_os_error(-r as i32)) }
+        else { Ok(r as usize) }
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+
+#[test]
+fn print_hwdeps() {
+    for a in super::card::Iter::new().map(|a| a.unwrap()) {
+        for b in Iter::new(&Ctl::from_card(&a, false).unwrap()).map(|b| b.unwrap()) {
+            println!("Hwdep {:?} (hw:{},{}) {} - {}", b.get_iface(), a.get_index(), b.get_device(),
+                 a.get_name().unwrap(), b.get_name().unwrap())
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use alsa;
 use super::error::*;
 use std::{slice, ptr, fmt};
+use std::ffi::CString;
+use libc::{c_char, ssize_t};
 
 /// [snd_output_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___output.html) wrapper
 pub struct Output(*mut alsa::snd_output_t);
@@ -47,3 +49,32 @@ impl fmt::Display for Output {
 }
 
 pub fn output_handle(o: &Output) -> *mut alsa::snd_output_t { o.0 }
+
+
+/// [snd_input_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___input.html) wrapper
+pub struct Input(*mut alsa::snd_input_t);
+
+unsafe impl Send for Input {}
+
+impl Drop for Input {
+    fn drop(&mut self) { unsafe { alsa::snd_input_close(self.0) }; }
+}
+
+impl Input {
+
+    /// Reads from a copy of the buffer.
+    pub fn buffer_open(buf: &[u8]) -> Result<Input> {
+        let mut q = ptr::null_mut();
+        acheck!(snd_input_buffer_open(&mut q, buf.as_ptr() as *const c_char, buf.len() as ssize_t)).map(|_| Input(q))
+    }
+
+    /// Reads from a file, e g, "/etc/asound.conf".
+    pub fn file_open(path: &str) -> Result<Input> {
+        let mut q = ptr::null_mut();
+        let p = CString::new(path).unwrap();
+        let m = CString::new("r").unwrap();
+        acheck!(snd_input_stdio_open(&mut q, p.as_ptr(), m.as_ptr())).map(|_| Input(q))
+    }
+}
+
+pub fn input_handle(i: &Input) -> *mut alsa::snd_input_t { i.0 }
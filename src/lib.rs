@@ -86,15 +86,21 @@ pub enum Round {
 }
 
 mod error;
-pub use error::{Error, Result};
+pub use error::{Error, ErrorKind, Result};
+
+mod lib_error;
+pub use lib_error::{LibError, set_error_handler, silence_errors, reset_error_handler};
 
 pub mod card;
 pub use card::Card as Card;
 
 mod ctl_int;
+mod tlv;
 pub mod ctl {
     //! Control device API
-    pub use super::ctl_int::{Ctl, CardInfo, ElemIface, ElemId, ElemType, ElemValue, ElemInfo};
+    pub use super::ctl_int::{Ctl, CardInfo, PowerState, ElemIface, ElemId, ElemType, ElemValue, ElemInfo, ElemList, ElemListIter, UserElem, Iec958, Iec958Status,
+        Event, EventMask, CtlEvent, EventStream};
+    pub use super::tlv::{Tlv, DbScale, DbRangeItem, DB_GAIN_MUTE};
 }
 
 pub use ctl::Ctl as Ctl;
@@ -102,16 +108,29 @@ pub use ctl::Ctl as Ctl;
 pub mod hctl;
 pub use hctl::HCtl as HCtl;
 
+pub mod state;
+
+pub mod ucm;
+
+pub mod config;
+
 pub mod pcm;
 pub use pcm::PCM as PCM;
 
 pub mod rawmidi;
 pub use rawmidi::Rawmidi as Rawmidi;
 
+pub mod timer;
+pub use timer::Timer as Timer;
+
+pub mod hwdep;
+pub use hwdep::Hwdep as Hwdep;
+
 pub mod device_name;
 
 pub mod poll;
 pub use poll::PollDescriptors as PollDescriptors;
+pub use poll::EventSource as EventSource;
 
 pub mod mixer;
 pub use mixer::Mixer as Mixer;
@@ -119,8 +138,10 @@ pub use mixer::Mixer as Mixer;
 pub mod seq;
 pub use seq::Seq as Seq;
 
+pub mod smf;
+
 mod io;
-pub use io::Output;
+pub use io::{Output, Input};
 
 // Reexported inside PCM module
 mod chmap;
@@ -0,0 +1,26 @@
+#include <stdarg.h>
+#include <stdio.h>
+
+typedef void (*alsa_rs_error_cb)(const char *file, int line, const char *function, int err, const char *msg);
+
+static alsa_rs_error_cb callback;
+
+void alsa_rs_set_error_callback(alsa_rs_error_cb cb)
+{
+	callback = cb;
+}
+
+/* Matches snd_lib_error_handler_t */
+void alsa_rs_error_shim(const char *file, int line, const char *function, int err, const char *fmt, ...)
+{
+	char buf[1024];
+	va_list ap;
+	alsa_rs_error_cb cb = callback;
+
+	if (!cb)
+		return;
+	va_start(ap, fmt);
+	vsnprintf(buf, sizeof(buf), fmt, ap);
+	va_end(ap);
+	cb(file, line, function, err, buf);
+}
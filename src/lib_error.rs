@@ -0,0 +1,134 @@
+//! Routing of alsa-lib's internal error messages (the ones printed as "ALSA lib pcm.c:...") to Rust
+
+use libc::{c_char, c_int};
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex, Once, ONCE_INIT};
+use std::{fmt, mem, panic, ptr};
+use nix;
+use alsa;
+
+type Callback = extern "C" fn(*const c_char, c_int, *const c_char, c_int, *const c_char);
+
+extern "C" {
+    // In src/lib_error.c
+    fn alsa_rs_set_error_callback(cb: Option<Callback>);
+    fn alsa_rs_error_shim(file: *const c_char, line: c_int, function: *const c_char, err: c_int, fmt: *const c_char, ...);
+}
+
+/// An error message from alsa-lib, see `set_error_handler`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibError {
+    /// Source file inside alsa-lib, e g, "pcm.c"
+    pub file: String,
+    pub line: i32,
+    /// Function inside alsa-lib, e g, "snd_pcm_open_noupdate"
+    pub function: String,
+    /// The errno, for messages that are caused by a failing system call
+    pub errno: Option<nix::Errno>,
+    /// The formatted message
+    pub message: String,
+}
+
+impl fmt::Display for LibError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "ALSA lib {}:{}:({}) {}", self.file, self.line, self.function, self.message));
+        if let Some(e) = self.errno { try!(write!(f, ": {}", e.desc())) };
+        Ok(())
+    }
+}
+
+type Handler = Option<Arc<Fn(LibError) + Send + Sync>>;
+
+fn handler() -> &'static Mutex<Handler> {
+    static INIT: Once = ONCE_INIT;
+    static mut H: *const Mutex<Handler> = 0 as *const _;
+    unsafe {
+        INIT.call_once(|| H = Box::into_raw(Box::new(Mutex::new(None))));
+        &*H
+    }
+}
+
+fn cstr_lossy(s: *const c_char) -> String {
+    if s == ptr::null() { String::new() } else { unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned() }
+}
+
+extern "C" fn callback(file: *const c_char, line: c_int, function: *const c_char, err: c_int, msg: *const c_char) {
+    let e = LibError {
+        file: cstr_lossy(file),
+        line: line as i32,
+        function: cstr_lossy(function),
+        errno: if err != 0 { Some(nix::Errno::from_i32(err.abs())) } else { None },
+        message: cstr_lossy(msg),
+    };
+    // Don't hold the lock while calling the handler, it might want to replace itself
+    let h = match handler().lock() { Ok(h) => h.clone(), Err(_) => return };
+    if let Some(f) = h {
+        // Don't unwind into C code
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| f(e)));
+    }
+}
+
+fn install(h: Handler, shim: bool) {
+    *handler().lock().unwrap_or_else(|e| e.into_inner()) = h;
+    unsafe {
+        if shim {
+            alsa_rs_set_error_callback(Some(callback));
+            alsa::snd_lib_error_set_handler(Some(mem::transmute(alsa_rs_error_shim as
+                unsafe extern "C" fn(*const c_char, c_int, *const c_char, c_int, *const c_char, ...))));
+        } else {
+            alsa::snd_lib_error_set_handler(None);
+            alsa_rs_set_error_callback(None);
+        }
+    }
+}
+
+/// Calls `f` for every error message from alsa-lib, instead of printing it to stderr.
+///
+/// The handler is process wide, and may be called from any thread that calls into alsa-lib.
+/// It may itself call `set_error_handler`, `silence_errors` or `reset_error_handler`.
+pub fn set_error_handler(f: Box<Fn(LibError) + Send + Sync>) { install(Some(Arc::from(f)), true) }
+
+/// Discards all error messages from alsa-lib.
+pub fn silence_errors() { install(None, true) }
+
+/// Restores alsa-lib's default behaviour of printing error messages to stderr.
+pub fn reset_error_handler() { install(None, false) }
+
+/// The handler is process wide, so tests that install one must not run concurrently.
+#[cfg(test)]
+fn with_handler<F: FnOnce()>(h: Box<Fn(LibError) + Send + Sync>, f: F) {
+    static INIT: Once = ONCE_INIT;
+    static mut LOCK: *const Mutex<()> = 0 as *const _;
+    let _g = unsafe {
+        INIT.call_once(|| LOCK = Box::into_raw(Box::new(Mutex::new(()))));
+        (*LOCK).lock().unwrap_or_else(|e| e.into_inner())
+    };
+    set_error_handler(h);
+    let r = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    reset_error_handler();
+    if let Err(e) = r { panic::resume_unwind(e) }
+}
+
+#[test]
+fn lib_error_handler() {
+    use std::ffi::CString;
+    let v = Arc::new(Mutex::new(vec!()));
+    let v2 = v.clone();
+    with_handler(Box::new(move |e| v2.lock().unwrap().push(e)), || {
+        assert!(::PCM::open(&*CString::new("this_PCM_does_not_exist_either").unwrap(), ::Direction::Playback, false).is_err());
+    });
+    let v = v.lock().unwrap();
+    let e = v.iter().find(|e| e.message.contains("this_PCM_does_not_exist_either")).unwrap();
+    println!("{}", e);
+    assert!(e.file.starts_with("pcm"));
+    assert!(e.line > 0);
+}
+
+#[test]
+fn lib_error_handler_reentrant() {
+    use std::ffi::CString;
+    // A handler that replaces itself must not deadlock
+    with_handler(Box::new(|_| silence_errors()), || {
+        assert!(::PCM::open(&*CString::new("this_PCM_does_not_exist_either").unwrap(), ::Direction::Playback, false).is_err());
+    });
+}
@@ -2,8 +2,12 @@
 //!
 use std::ffi::{CStr, CString};
 use std::{ptr, mem, fmt, ops};
-use libc::{c_long, c_int, c_uint, c_short, pollfd};
+use libc::{c_long, c_int, c_uint, c_short, c_void, pollfd};
+use std::collections::VecDeque;
+use futures::{Stream, Async};
+use futures::task::Context;
 use poll;
+use ctl_int::EventMask;
 
 use alsa;
 use super::Round;
@@ -60,6 +64,84 @@ impl Mixer {
 
     pub fn wait(&self, timeout_ms: Option<u32>) -> Result<bool> {
         acheck!(snd_mixer_wait(self.0, timeout_ms.map(|x| x as c_int).unwrap_or(-1))).map(|i| i == 1) }
+
+    pub fn handle_events(&self) -> Result<u32> { acheck!(snd_mixer_handle_events(self.0)).map(|x| x as u32) }
+
+    /// Returns a stream of changes to simple elements: value and info changes, as well as added and removed elements.
+    ///
+    /// This sets the mixer and element callbacks, so only one stream can be active for a Mixer at any one time;
+    /// if there already is one, this fails with EBUSY.
+    /// Callbacks set directly through alsa-sys are replaced, and cleared when the stream is dropped.
+    pub fn event_stream<'a>(&'a self) -> Result<EventStream<'a>> {
+        if !unsafe { alsa::snd_mixer_get_callback_private(self.0) }.is_null() {
+            return Err(Error::new("snd_mixer_set_callback", ::libc::EBUSY))
+        }
+        let mut q: Box<EventQueue> = Box::new(VecDeque::new());
+        let p = &mut *q as *mut EventQueue as *mut c_void;
+        unsafe {
+            alsa::snd_mixer_set_callback_private(self.0, p);
+            alsa::snd_mixer_set_callback(self.0, Some(mixer_callback));
+        }
+        for e in self.iter() { set_elem_callback(e.handle, p) }
+        Ok(EventStream(self, q, poll::ReadyWaker::new()))
+    }
+}
+
+type EventQueue = VecDeque<SelemEvent>;
+
+fn set_elem_callback(e: *mut alsa::snd_mixer_elem_t, p: *mut c_void) {
+    unsafe {
+        alsa::snd_mixer_elem_set_callback_private(e, p);
+        alsa::snd_mixer_elem_set_callback(e, if p.is_null() { None } else { Some(elem_callback) });
+    }
+}
+
+extern "C" fn elem_callback(e: *mut alsa::snd_mixer_elem_t, mask: c_uint) -> c_int {
+    let q = unsafe { alsa::snd_mixer_elem_get_callback_private(e) } as *mut EventQueue;
+    if q.is_null() || unsafe { alsa::snd_mixer_elem_get_type(e) } != alsa::SND_MIXER_ELEM_SIMPLE { return 0 }
+    let id = SelemId::empty();
+    unsafe { alsa::snd_mixer_selem_get_id(e, id.as_ptr()) };
+    unsafe { (*q).push_back(SelemEvent { id: id, mask: EventMask(mask as u32) }) };
+    0
+}
+
+extern "C" fn mixer_callback(m: *mut alsa::snd_mixer_t, mask: c_uint, e: *mut alsa::snd_mixer_elem_t) -> c_int {
+    let p = unsafe { alsa::snd_mixer_get_callback_private(m) };
+    if p.is_null() { return 0 }
+    set_elem_callback(e, p);
+    elem_callback(e, mask)
+}
+
+/// A change to a simple element, as yielded by `Mixer::event_stream`
+pub struct SelemEvent {
+    pub id: SelemId,
+    pub mask: EventMask,
+}
+
+/// A `futures::Stream` of simple element changes, see `Mixer::event_stream`
+pub struct EventStream<'a>(&'a Mixer, Box<EventQueue>, poll::ReadyWaker);
+
+impl<'a> Drop for EventStream<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            alsa::snd_mixer_set_callback((self.0).0, None);
+            alsa::snd_mixer_set_callback_private((self.0).0, ptr::null_mut());
+        }
+        for e in self.0.iter() { set_elem_callback(e.handle, ptr::null_mut()) }
+    }
+}
+
+impl<'a> Stream for EventStream<'a> {
+    type Item = SelemEvent;
+    type Error = Error;
+
+    fn poll_next(&mut self, cx: &mut Context) -> Result<Async<Option<SelemEvent>>> {
+        loop {
+            if let Some(e) = self.1.pop_front() { return Ok(Async::Ready(Some(e))) }
+            if !try!(self.2.ready_or_wake(self.0, cx)) { return Ok(Async::Pending) }
+            try!(self.0.handle_events());
+        }
+    }
 }
 
 /// Closes mixer and frees used resources
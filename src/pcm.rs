@@ -51,6 +51,7 @@ use std::ffi::{CStr, CString};
 use std::{io, fmt, ptr, cell};
 use super::error::*;
 use super::{Direction, Output, poll, ValueOr, chmap};
+use super::config::{Config, config_ptr};
 
 pub use super::chmap::{Chmap, ChmapPosition, ChmapType, ChmapsQuery};
 
@@ -131,6 +132,17 @@ impl PCM {
         acheck!(snd_pcm_open(&mut r, name.as_ptr(), stream, flags)).map(|_| PCM(r, cell::Cell::new(false)))
     }
 
+    /// Like `open`, but looks up the PCM name in `lconf` instead of the global configuration.
+    pub fn open_lconf(name: &CStr, dir: Direction, nonblock: bool, lconf: &Config) -> Result<PCM> {
+        let mut r = ptr::null_mut();
+        let stream = match dir {
+            Direction::Capture => alsa::SND_PCM_STREAM_CAPTURE,
+            Direction::Playback => alsa::SND_PCM_STREAM_PLAYBACK
+        };
+        let flags = if nonblock { alsa::SND_PCM_NONBLOCK } else { 0 };
+        acheck!(snd_pcm_open_lconf(&mut r, name.as_ptr(), stream, flags, config_ptr(lconf))).map(|_| PCM(r, cell::Cell::new(false)))
+    }
+
     pub fn start(&self) -> Result<()> { acheck!(snd_pcm_start(self.0)).map(|_| ()) }
     pub fn drop(&self) -> Result<()> { acheck!(snd_pcm_drop(self.0)).map(|_| ()) }
     pub fn pause(&self, pause: bool) -> Result<()> {
@@ -364,7 +376,7 @@ impl<'a, S: Copy> io::Read for IO<'a, S> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let size = self.0.bytes_to_frames(buf.len() as isize) as alsa::snd_pcm_uframes_t; // TODO: Do we need to check for overflow here?
         let r = unsafe { alsa::snd_pcm_readi((self.0).0, buf.as_mut_ptr() as *mut c_void, size) };
-        if r < 0 { Err(io::Error::from_raw_os_error(r as i32)) }
+        if r < 0 { Err(io::Error::from_raw_os_error(-r as i32)) }
         else { Ok(self.0.frames_to_bytes(r) as usize) }
     }
 }
@@ -373,7 +385,7 @@ impl<'a, S: Copy> io::Write for IO<'a, S> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let size = self.0.bytes_to_frames(buf.len() as isize) as alsa::snd_pcm_uframes_t; // TODO: Do we need to check for overflow here?
         let r = unsafe { alsa::snd_pcm_writei((self.0).0, buf.as_ptr() as *const c_void, size) };
-        if r < 0 { Err(io::Error::from_raw_os_error(r as i32)) }
+        if r < 0 { Err(io::Error::from_raw_os_error(-r as i32)) }
         else { Ok(self.0.frames_to_bytes(r) as usize) }
     }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
@@ -6,6 +6,11 @@ use libc;
 use super::error::*;
 use std::io;
 use libc::pollfd;
+use std::thread;
+use std::sync::{Arc, Mutex, Condvar};
+use std::os::unix::io::RawFd;
+use futures::task::{self, Context};
+use nix;
 
 
 bitflags! {
@@ -32,6 +37,12 @@ pub trait PollDescriptors {
     }
 }
 
+impl<'a, T: PollDescriptors + ?Sized> PollDescriptors for &'a T {
+    fn count(&self) -> usize { (**self).count() }
+    fn fill(&self, a: &mut [pollfd]) -> Result<usize> { (**self).fill(a) }
+    fn revents(&self, a: &[pollfd]) -> Result<PollFlags> { (**self).revents(a) }
+}
+
 impl PollDescriptors for pollfd {
     fn count(&self) -> usize { 1 }
     fn fill(&self, a: &mut [pollfd]) -> Result<usize> { a[0] = self.clone(); Ok(1) }
@@ -66,3 +77,363 @@ pub fn poll_all<'a>(desc: &[&'a PollDescriptors], timeout: i32) -> Result<Vec<(&
     }
     Ok(res)
 }
+
+/// Integration with external event loops (epoll, mio, ...)
+///
+/// Register every fd returned by `fds` with the event loop, for the given events. When any of them fires,
+/// collect the revents of all of them (in the same order) and call `translate` to find out what happened
+/// to the handle. Do not use the raw revents directly: e g, a PCM using the dmix plugin signals POLLIN
+/// on a timer fd when it is ready for writing.
+///
+/// `Ctl`, `HCtl`, `Rawmidi`, `Seq`, `Timer` and `Hwdep` normally have a single fd which stays the same for the
+/// handle's lifetime; `raw_fd` returns it, so it can be registered directly.
+/// A `PCM` can have several fds depending on its plugin chain, and a `Mixer` has one fd per attached card;
+/// for these, call `fds` again if `count` changes, e g, after attaching another card to the mixer.
+pub trait EventSource {
+    /// The file descriptors, and the events to wait for on each of them.
+    fn fds(&self) -> Result<Vec<(RawFd, PollFlags)>>;
+    /// Translates the revents of each fd (in the order returned by `fds`) into events on the handle.
+    fn translate(&self, revents: &[PollFlags]) -> Result<PollFlags>;
+    /// Returns the fd, if there is exactly one.
+    fn raw_fd(&self) -> Option<RawFd> {
+        match self.fds() { Ok(ref v) if v.len() == 1 => Some(v[0].0), _ => None }
+    }
+}
+
+impl<T: PollDescriptors + ?Sized> EventSource for T {
+    fn fds(&self) -> Result<Vec<(RawFd, PollFlags)>> {
+        Ok(try!(self.get()).into_iter().map(|p| (p.fd, PollFlags::from_bits_truncate(p.events))).collect())
+    }
+    fn translate(&self, revents: &[PollFlags]) -> Result<PollFlags> {
+        let mut v = try!(self.get());
+        if v.len() != revents.len() { return Err(Error::new("EventSource::translate", libc::EINVAL)) }
+        for (p, r) in v.iter_mut().zip(revents) { p.revents = r.bits(); }
+        self.revents(&v)
+    }
+}
+
+fn os_error(func: &'static str) -> Error {
+    Error::new(func, io::Error::last_os_error().raw_os_error().unwrap_or(0))
+}
+
+/// Identifies a handle registered with a `Poller`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(pub usize);
+
+struct Source<'a> {
+    d: Box<PollDescriptors + 'a>,
+    token: Token,
+    start: usize,
+    count: usize,
+}
+
+/// Waits for several handles at once, without allocating on every call.
+///
+/// Register handles (PCM, Ctl, HCtl, Mixer, Rawmidi, `(&Seq, Option<Direction>)`, ...) once, each with
+/// a token, then call `poll` in a loop. The poll descriptors are refilled before every poll;
+/// if a handle's `count` changes, storage is rebuilt.
+///
+/// # Example
+/// ```no_run
+/// use alsa::poll::{Poller, Token};
+/// use alsa::{PCM, Ctl, Direction};
+///
+/// let pcm = PCM::new("default", Direction::Playback, true).unwrap();
+/// let ctl = Ctl::new("default", true).unwrap();
+/// let mut p = Poller::new();
+/// p.register(&pcm, Token(0)).unwrap();
+/// p.register(&ctl, Token(1)).unwrap();
+/// loop {
+///     for &(token, flags) in p.poll(-1).unwrap() {
+///         println!("{:?} is ready: {:?}", token, flags);
+///     }
+/// }
+/// ```
+pub struct Poller<'a> {
+    sources: Vec<Source<'a>>,
+    fds: Vec<pollfd>,
+    ready: Vec<(Token, PollFlags)>,
+    epoll: Option<Epoll>,
+}
+
+impl<'a> Poller<'a> {
+    /// Creates a poller that uses the poll system call.
+    pub fn new() -> Poller<'a> { Poller { sources: vec!(), fds: vec!(), ready: vec!(), epoll: None } }
+
+    /// Creates a poller that uses epoll, which scales better when there are many handles.
+    ///
+    /// With epoll, a file descriptor can only be registered once, so e g a `Seq` should be registered with
+    /// `None` as direction rather than once per direction.
+    pub fn with_epoll() -> Result<Poller<'a>> {
+        let e = try!(Epoll::new());
+        Ok(Poller { epoll: Some(e), ..Poller::new() })
+    }
+
+    pub fn register<P: PollDescriptors + 'a>(&mut self, p: P, token: Token) -> Result<()> {
+        if self.sources.iter().any(|s| s.token == token) { return Err(Error::new("Poller::register", libc::EEXIST)) }
+        self.sources.push(Source { d: Box::new(p), token: token, start: 0, count: 0 });
+        let n = self.sources.len();
+        self.ready.reserve(n);
+        self.layout()
+    }
+
+    pub fn deregister(&mut self, token: Token) -> Result<()> {
+        let i = try!(self.sources.iter().position(|s| s.token == token).ok_or_else(|| Error::new("Poller::deregister", libc::ENOENT)));
+        self.sources.remove(i);
+        self.layout()
+    }
+
+    /// Number of registered handles
+    pub fn len(&self) -> usize { self.sources.len() }
+
+    fn layout(&mut self) -> Result<()> {
+        let mut n = 0;
+        for s in self.sources.iter_mut() {
+            s.start = n;
+            s.count = s.d.count();
+            n += s.count;
+        }
+        self.fds.resize(n, pollfd { fd: -1, events: 0, revents: 0 });
+        self.fill()
+    }
+
+    fn fill(&mut self) -> Result<()> {
+        for s in self.sources.iter() {
+            let f = &mut self.fds[s.start..s.start+s.count];
+            if try!(s.d.fill(f)) != s.count { return Err(Error::unsupported("did not fill the poll descriptors array")) }
+        }
+        for f in self.fds.iter_mut() { f.revents = 0; }
+        match self.epoll {
+            Some(ref mut e) => e.sync(&self.fds),
+            None => Ok(()),
+        }
+    }
+
+    /// Waits until at least one handle is ready, or timeout (in milliseconds, -1 means forever) expires.
+    ///
+    /// Returns the tokens of the ready handles, with events as translated by their `revents` method.
+    pub fn poll(&mut self, timeout: i32) -> Result<&[(Token, PollFlags)]> {
+        if self.sources.iter().any(|s| s.d.count() != s.count) { try!(self.layout()) }
+        else { try!(self.fill()) }
+        match self.epoll {
+            Some(ref mut e) => try!(e.wait(&mut self.fds, timeout)),
+            None => try!(poll(&mut self.fds, timeout)),
+        };
+        self.ready.clear();
+        for s in self.sources.iter() {
+            let f = &self.fds[s.start..s.start+s.count];
+            if f.iter().all(|p| p.revents == 0) { continue }
+            let r = try!(s.d.revents(f));
+            if !r.is_empty() { self.ready.push((s.token, r)) }
+        }
+        Ok(&self.ready)
+    }
+}
+
+struct Epoll {
+    fd: libc::c_int,
+    registered: Vec<pollfd>,
+    events: Vec<libc::epoll_event>,
+}
+
+impl Drop for Epoll {
+    fn drop(&mut self) { unsafe { libc::close(self.fd) }; }
+}
+
+impl Epoll {
+    fn new() -> Result<Epoll> {
+        let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if fd < 0 { return Err(os_error("epoll_create1")) }
+        Ok(Epoll { fd: fd, registered: vec!(), events: vec!() })
+    }
+
+    fn ctl(&self, op: libc::c_int, p: &pollfd, index: usize) -> Result<()> {
+        // POLLxxx and EPOLLxxx have the same values on Linux
+        let mut e = libc::epoll_event { events: p.events as u16 as u32, u64: index as u64 };
+        if unsafe { libc::epoll_ctl(self.fd, op, p.fd, &mut e) } < 0 { Err(os_error("epoll_ctl")) } else { Ok(()) }
+    }
+
+    /// Updates the epoll set to match fds, touching only the entries that changed.
+    fn sync(&mut self, fds: &[pollfd]) -> Result<()> {
+        let changed = |a: Option<&pollfd>, b: &pollfd| a.map(|a| a.fd != b.fd || a.events != b.events).unwrap_or(true);
+        for (i, old) in self.registered.iter().enumerate() {
+            // The fd might already be closed, so ignore errors
+            if changed(fds.get(i), old) { let _ = self.ctl(libc::EPOLL_CTL_DEL, old, i); }
+        }
+        for (i, new) in fds.iter().enumerate() {
+            if changed(self.registered.get(i), new) { try!(self.ctl(libc::EPOLL_CTL_ADD, new, i)); }
+        }
+        self.registered.clear();
+        self.registered.extend_from_slice(fds);
+        let n = ::std::cmp::max(fds.len(), 1);
+        self.events.resize(n, libc::epoll_event { events: 0, u64: 0 });
+        Ok(())
+    }
+
+    fn wait(&mut self, fds: &mut [pollfd], timeout: i32) -> Result<usize> {
+        let r = unsafe { libc::epoll_wait(self.fd, self.events.as_mut_ptr(), self.events.len() as libc::c_int, timeout) };
+        if r < 0 { return Err(os_error("epoll_wait")) }
+        for e in &self.events[..r as usize] {
+            let (ev, index) = (e.events, e.u64);
+            fds[index as usize].revents = ev as libc::c_short;
+        }
+        Ok(r as usize)
+    }
+}
+
+struct WakeState {
+    fds: Vec<pollfd>,
+    waker: Option<task::Waker>,
+    quit: bool,
+}
+
+struct WakeThread {
+    state: Arc<(Mutex<WakeState>, Condvar)>,
+    /// Read and write ends of the pipe used to interrupt the thread's `poll`
+    pipe: [RawFd; 2],
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl WakeThread {
+    fn new() -> Result<WakeThread> {
+        let mut pipe = [0; 2];
+        if unsafe { libc::pipe2(pipe.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK) } < 0 { return Err(os_error("pipe2")) }
+        let state = Arc::new((Mutex::new(WakeState { fds: vec!(), waker: None, quit: false }), Condvar::new()));
+        let (s2, cancel) = (state.clone(), pipe[0]);
+        let t = thread::Builder::new().name("alsa-rs waker".into()).spawn(move || wake_thread(&s2, cancel));
+        match t {
+            Ok(t) => Ok(WakeThread { state: state, pipe: pipe, thread: Some(t) }),
+            Err(e) => {
+                unsafe { libc::close(pipe[0]); libc::close(pipe[1]); }
+                Err(Error::new("pthread_create", e.raw_os_error().unwrap_or(libc::EAGAIN)))
+            }
+        }
+    }
+
+    fn interrupt(&self) { unsafe { libc::write(self.pipe[1], b"x".as_ptr() as *const _, 1) }; }
+}
+
+impl Drop for WakeThread {
+    fn drop(&mut self) {
+        self.state.0.lock().unwrap_or_else(|e| e.into_inner()).quit = true;
+        self.state.1.notify_one();
+        self.interrupt();
+        if let Some(t) = self.thread.take() { let _ = t.join(); }
+        unsafe { libc::close(self.pipe[0]); libc::close(self.pipe[1]); }
+    }
+}
+
+fn wake_thread(state: &(Mutex<WakeState>, Condvar), cancel: RawFd) {
+    let mut fds = vec!();
+    loop {
+        {
+            let mut s = state.0.lock().unwrap_or_else(|e| e.into_inner());
+            while s.waker.is_none() && !s.quit { s = state.1.wait(s).unwrap_or_else(|e| e.into_inner()); }
+            if s.quit { return }
+            fds.clear();
+            fds.extend_from_slice(&s.fds);
+        }
+        let n = fds.len();
+        fds.push(pollfd { fd: cancel, events: libc::POLLIN, revents: 0 });
+        // On error, wake up the task anyway and let it find out what is wrong
+        let ready = match poll(&mut fds, -1) {
+            Ok(_) => fds[..n].iter().any(|p| p.revents != 0),
+            Err(e) => e.errno() != Some(nix::Errno::EINTR),
+        };
+        if fds[n].revents != 0 {
+            let mut buf = [0u8; 16];
+            while unsafe { libc::read(cancel, buf.as_mut_ptr() as *mut _, buf.len()) } > 0 {}
+        }
+        let mut s = state.0.lock().unwrap_or_else(|e| e.into_inner());
+        if s.quit { return }
+        if ready { if let Some(w) = s.waker.take() { w.wake() } }
+    }
+}
+
+/// Wakes up a task once a handle has something to read. Used by the `futures::Stream` implementations in this crate.
+///
+/// Since there is no reactor to register with, this is done by a helper thread that waits in `poll` on the
+/// handle's file descriptors. The thread is started on first use, and is stopped when this struct is dropped,
+/// so drop it before closing the handle.
+pub(crate) struct ReadyWaker(Option<WakeThread>);
+
+impl ReadyWaker {
+    pub fn new() -> ReadyWaker { ReadyWaker(None) }
+
+    /// Checks, without blocking, whether there is something to read from `d`.
+    ///
+    /// If not, the task in `cx` will be woken up once there is.
+    pub fn ready_or_wake(&mut self, d: &PollDescriptors, cx: &mut Context) -> Result<bool> {
+        let mut fds = try!(d.get());
+        try!(poll(&mut fds, 0));
+        if !try!(d.revents(&fds)).is_empty() { return Ok(true) }
+        for p in fds.iter_mut() { p.revents = 0 }
+        if self.0.is_none() { self.0 = Some(try!(WakeThread::new())) }
+        let t = self.0.as_ref().unwrap();
+        let was_polling = {
+            let mut s = t.state.0.lock().unwrap_or_else(|e| e.into_inner());
+            let changed = s.fds.len() != fds.len() || s.fds.iter().zip(&fds).any(|(a, b)| a.fd != b.fd || a.events != b.events);
+            s.fds = fds;
+            let had_waker = s.waker.replace(cx.waker().clone()).is_some();
+            changed && had_waker
+        };
+        // The thread picks up the new waker by itself, but needs to be interrupted if the fds changed
+        if was_polling { t.interrupt() }
+        t.state.1.notify_one();
+        Ok(false)
+    }
+}
+
+#[test]
+fn poller_pipe() {
+    for &epoll in &[false, true] {
+        let mut a = [0; 2];
+        let mut b = [0; 2];
+        assert_eq!(unsafe { libc::pipe(a.as_mut_ptr()) }, 0);
+        assert_eq!(unsafe { libc::pipe(b.as_mut_ptr()) }, 0);
+        {
+            let mut p = if epoll { Poller::with_epoll().unwrap() } else { Poller::new() };
+            p.register(pollfd { fd: a[0], events: libc::POLLIN, revents: 0 }, Token(1)).unwrap();
+            p.register(pollfd { fd: b[0], events: libc::POLLIN, revents: 0 }, Token(2)).unwrap();
+            assert!(p.register(pollfd { fd: b[1], events: libc::POLLOUT, revents: 0 }, Token(2)).is_err());
+            assert!(p.poll(0).unwrap().is_empty());
+            assert_eq!(unsafe { libc::write(b[1], b"x".as_ptr() as *const _, 1) }, 1);
+            assert_eq!(p.poll(100).unwrap(), &[(Token(2), POLLIN)]);
+            p.deregister(Token(2)).unwrap();
+            assert!(p.poll(0).unwrap().is_empty());
+            assert_eq!(p.len(), 1);
+        }
+        for &fd in a.iter().chain(b.iter()) { unsafe { libc::close(fd) }; }
+    }
+}
+
+#[test]
+fn event_source_pipe() {
+    let mut a = [0; 2];
+    assert_eq!(unsafe { libc::pipe(a.as_mut_ptr()) }, 0);
+    let p = pollfd { fd: a[0], events: libc::POLLIN, revents: 0 };
+    assert_eq!(p.fds().unwrap(), vec!((a[0], POLLIN)));
+    assert_eq!(p.translate(&[POLLIN | POLLHUP]).unwrap(), POLLIN | POLLHUP);
+    assert!(p.translate(&[]).is_err());
+    assert_eq!(p.raw_fd(), Some(a[0]));
+    for &fd in a.iter() { unsafe { libc::close(fd) }; }
+}
+
+#[test]
+fn ready_waker_pipe() {
+    use futures::{executor, future, Async};
+    let mut a = [0; 2];
+    assert_eq!(unsafe { libc::pipe(a.as_mut_ptr()) }, 0);
+    let p = pollfd { fd: a[0], events: libc::POLLIN, revents: 0 };
+    let mut w = ReadyWaker::new();
+    let wfd = a[1];
+    let t = thread::spawn(move || {
+        thread::sleep(::std::time::Duration::from_millis(50));
+        assert_eq!(unsafe { libc::write(wfd, b"x".as_ptr() as *const _, 1) }, 1);
+    });
+    executor::block_on(future::poll_fn(|cx| w.ready_or_wake(&p, cx)
+        .map(|r| if r { Async::Ready(()) } else { Async::Pending }))).unwrap();
+    t.join().unwrap();
+    drop(w);
+    for &fd in a.iter() { unsafe { libc::close(fd) }; }
+}
@@ -2,8 +2,9 @@
 
 use libc::{c_uint, c_int, c_short, c_uchar, c_void, c_long, size_t, pollfd};
 use super::error::*;
+use std::os::unix::io::RawFd;
 use alsa;
-use super::{Direction, poll};
+use super::{Direction, poll, timer};
 use std::{ptr, fmt, mem, slice, time, cell};
 use std::ffi::{CStr};
 use std::borrow::Cow;
@@ -33,6 +34,22 @@ const SND_SEQ_EVENT_LENGTH_FIXED: u8 = (0<<2);
 const SND_SEQ_EVENT_LENGTH_MASK: u8 = (3<<2);
 const SND_SEQ_EVENT_LENGTH_VARIABLE: u8 = (1<<2);
 const SND_SEQ_EVENT_LENGTH_VARUSR: u8 = (2<<2);
+const SND_SEQ_REMOVE_INPUT: c_uint = 1<<0;
+const SND_SEQ_REMOVE_OUTPUT: c_uint = 1<<1;
+const SND_SEQ_REMOVE_DEST: c_uint = 1<<2;
+const SND_SEQ_REMOVE_DEST_CHANNEL: c_uint = 1<<3;
+const SND_SEQ_REMOVE_TIME_BEFORE: c_uint = 1<<4;
+const SND_SEQ_REMOVE_TIME_AFTER: c_uint = 1<<5;
+const SND_SEQ_REMOVE_TIME_TICK: c_uint = 1<<6;
+const SND_SEQ_REMOVE_EVENT_TYPE: c_uint = 1<<7;
+const SND_SEQ_REMOVE_IGNORE_OFF: c_uint = 1<<8;
+const SND_SEQ_REMOVE_TAG_MATCH: c_uint = 1<<9;
+
+// Not in alsa-sys
+extern "C" {
+    fn snd_seq_client_info_get_card(info: *const alsa::snd_seq_client_info_t) -> c_int;
+    fn snd_seq_client_info_get_pid(info: *const alsa::snd_seq_client_info_t) -> c_int;
+}
 
 // Workaround for improper alignment of snd_seq_ev_ext_t in alsa-sys
 #[repr(packed)]
@@ -46,7 +63,8 @@ struct EvExtPacked {
 /// To access the functions ``event_input`, `event_input_pending` and `set_input_buffer_size`,
 /// you first have to obtain an instance of `Input` by calling `input()`. Only one instance of
 /// `Input` may exist at any time for a given `Seq`.
-pub struct Seq(*mut alsa::snd_seq_t, cell::Cell<bool>);
+// The direction it was opened with, None meaning both
+pub struct Seq(*mut alsa::snd_seq_t, cell::Cell<bool>, Option<Direction>);
 
 unsafe impl Send for Seq {}
 
@@ -72,7 +90,7 @@ impl Seq {
             Some(Direction::Capture) => SND_SEQ_OPEN_INPUT,
         };
         acheck!(snd_seq_open(&mut h, n2.as_ptr(), streams, mode))
-            .map(|_| Seq(h, cell::Cell::new(false)))
+            .map(|_| Seq(h, cell::Cell::new(false), dir))
     }
 
     pub fn set_client_name(&self, name: &CStr) -> Result<()> {
@@ -83,18 +101,36 @@ impl Seq {
         acheck!(snd_seq_set_client_event_filter(self.0, event_type as c_int)).map(|_| ())
     }
 
+    #[deprecated(note = "use get_client_pool and set_client_pool instead")]
     pub fn set_client_pool_output(&self, size: u32) -> Result<()> {
         acheck!(snd_seq_set_client_pool_output(self.0, size as size_t)).map(|_| ())
     }
 
+    #[deprecated(note = "use get_client_pool and set_client_pool instead")]
     pub fn set_client_pool_input(&self, size: u32) -> Result<()> {
         acheck!(snd_seq_set_client_pool_input(self.0, size as size_t)).map(|_| ())
     }
 
+    #[deprecated(note = "use get_client_pool and set_client_pool instead")]
     pub fn set_client_pool_output_room(&self, size: u32) -> Result<()> {
         acheck!(snd_seq_set_client_pool_output_room(self.0, size as size_t)).map(|_| ())
     }
 
+    pub fn get_client_pool(&self) -> Result<ClientPool> {
+        let c = try!(ClientPool::new());
+        acheck!(snd_seq_get_client_pool(self.0, c.0)).map(|_| c)
+    }
+
+    /// Resizes the memory pools of this client. The client must be idle, i e, have no events queued.
+    pub fn set_client_pool(&self, c: &ClientPool) -> Result<()> {
+        acheck!(snd_seq_set_client_pool(self.0, c.0)).map(|_| ())
+    }
+
+    pub fn system_info(&self) -> Result<SystemInfo> {
+        let c = try!(SystemInfo::new());
+        acheck!(snd_seq_system_info(self.0, c.0)).map(|_| c)
+    }
+
     pub fn client_id(&self) -> Result<i32> {
         acheck!(snd_seq_client_id(self.0)).map(|q| q as i32)
     }
@@ -103,6 +139,17 @@ impl Seq {
         acheck!(snd_seq_drain_output(self.0)).map(|q| q as i32)
     }
 
+    /// Returns the information about this client.
+    pub fn get_client_info(&self) -> Result<ClientInfo> {
+        let c = try!(ClientInfo::new());
+        acheck!(snd_seq_get_client_info(self.0, c.0)).map(|_| c)
+    }
+
+    /// Sets the name, filters and error bounce of this client. The client field of `c` is ignored.
+    pub fn set_client_info(&self, c: &ClientInfo) -> Result<()> {
+        acheck!(snd_seq_set_client_info(self.0, c.0)).map(|_| ())
+    }
+
     pub fn get_any_client_info(&self, client: i32) -> Result<ClientInfo> {
         let c = try!(ClientInfo::new());
         acheck!(snd_seq_get_any_client_info(self.0, client, c.0)).map(|_| c)
@@ -140,6 +187,18 @@ impl Seq {
         acheck!(snd_seq_unsubscribe_port(self.0, z.0)).map(|_| ())
     }
 
+    /// Returns the subscription between `sender` and `dest`, or `None` if they are not connected.
+    pub fn get_port_subscription(&self, sender: Addr, dest: Addr) -> Result<Option<PortSubscribe>> {
+        let z = try!(PortSubscribe::new());
+        z.set_sender(sender);
+        z.set_dest(dest);
+        match acheck!(snd_seq_get_port_subscription(self.0, z.0)) {
+            Ok(_) => Ok(Some(z)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn control_queue(&self, q: i32, t: EventType, value: i32, e: Option<&mut Event>) -> Result<()> {
         assert!(EvQueueControl::<()>::has_data(t) || EvQueueControl::<i32>::has_data(t) || EvQueueControl::<u32>::has_data(t));
         let p = e.map(|e| &mut e.0 as *mut _).unwrap_or(ptr::null_mut());
@@ -176,15 +235,96 @@ impl Seq {
         acheck!(snd_seq_alloc_named_queue(self.0, n.as_ptr())).map(|q| q as i32)
     }
 
+    pub fn get_queue_status(&self, q: i32) -> Result<QueueStatus> {
+        let value = try!(QueueStatus::new());
+        acheck!(snd_seq_get_queue_status(self.0, q as c_int, value.0)).map(|_| value)
+    }
+
+    pub fn get_queue_info(&self, q: i32) -> Result<QueueInfo> {
+        let value = try!(QueueInfo::new());
+        acheck!(snd_seq_get_queue_info(self.0, q as c_int, value.0)).map(|_| value)
+    }
+
+    pub fn set_queue_info(&self, q: i32, value: &QueueInfo) -> Result<()> {
+        acheck!(snd_seq_set_queue_info(self.0, q as c_int, value.0)).map(|_| ())
+    }
+
+    /// Returns the queue with the given name.
+    pub fn query_named_queue(&self, n: &CStr) -> Result<i32> {
+        acheck!(snd_seq_query_named_queue(self.0, n.as_ptr())).map(|q| q as i32)
+    }
+
+    /// Returns true if this client is allowed to use the queue.
+    pub fn get_queue_usage(&self, q: i32) -> Result<bool> {
+        acheck!(snd_seq_get_queue_usage(self.0, q as c_int)).map(|used| used != 0)
+    }
+
+    pub fn set_queue_usage(&self, q: i32, used: bool) -> Result<()> {
+        acheck!(snd_seq_set_queue_usage(self.0, q as c_int, if used { 1 } else { 0 })).map(|_| ())
+    }
+
+    pub fn get_queue_timer(&self, q: i32) -> Result<QueueTimer> {
+        let value = try!(QueueTimer::new());
+        acheck!(snd_seq_get_queue_timer(self.0, q as c_int, value.0)).map(|_| value)
+    }
+
+    /// Selects the clock source of the queue. The queue must not be running.
+    pub fn set_queue_timer(&self, q: i32, value: &QueueTimer) -> Result<()> {
+        acheck!(snd_seq_set_queue_timer(self.0, q as c_int, value.0)).map(|_| ())
+    }
+
     pub fn sync_output_queue(&self) -> Result<()> {
         acheck!(snd_seq_sync_output_queue(self.0)).map(|_| ())
     }
 
+    /// Removes events matching `r` from the output buffer and the kernel's output queues.
+    ///
+    /// Removing input events would modify the input buffer, so this fails with EINVAL if `r.get_input()`
+    /// is set; use `Input::remove_events` for that.
+    pub fn remove_events(&self, r: &RemoveEvents) -> Result<()> {
+        if r.get_input() { return Err(Error::new("snd_seq_remove_events", ::libc::EINVAL)) }
+        acheck!(snd_seq_remove_events(self.0, r.0)).map(|_| ())
+    }
+
+    /// Removes all events from the output buffer and the kernel's output queues.
+    pub fn drop_output(&self) -> Result<()> {
+        acheck!(snd_seq_drop_output(self.0)).map(|_| ())
+    }
+
+    /// Removes all events from the output buffer, but not those already sent to the kernel.
+    pub fn drop_output_buffer(&self) -> Result<()> {
+        acheck!(snd_seq_drop_output_buffer(self.0)).map(|_| ())
+    }
+
+    /// Returns the number of bytes in the output buffer, i e, not yet sent with `drain_output`.
+    pub fn event_output_pending(&self) -> u32 {
+        unsafe { alsa::snd_seq_event_output_pending(self.0) as u32 }
+    }
+
+    pub fn get_output_buffer_size(&self) -> usize { unsafe { alsa::snd_seq_get_output_buffer_size(self.0) as usize } }
+    pub fn get_input_buffer_size(&self) -> usize { unsafe { alsa::snd_seq_get_input_buffer_size(self.0) as usize } }
+
+    /// Resizes the output buffer, in bytes. Events in the buffer are discarded.
+    pub fn set_output_buffer_size(&self, size: usize) -> Result<()> {
+        acheck!(snd_seq_set_output_buffer_size(self.0, size as size_t)).map(|_| ())
+    }
+
     /// Call this function to obtain an instance of `Input` to access the functions `event_input`,
     /// `event_input_pending` and `set_input_buffer_size`. See the documentation of `Input` for details.
     pub fn input<'a>(&'a self) -> Input<'a> {
         Input::new(self)
     }
+
+    /// Turns the sequencer into a `'static` stream of input events.
+    ///
+    /// Fails with EINVAL if the sequencer was not opened for input.
+    pub fn into_stream(self) -> Result<OwnedInputStream> {
+        // alsa-lib asserts on input functions called on an output-only handle, so don't ask it
+        if self.2 == Some(Direction::Playback) {
+            return Err(Error::new("Seq::into_stream", ::libc::EINVAL))
+        }
+        Ok(OwnedInputStream(poll::ReadyWaker::new(), self))
+    }
 }
 
 /// Struct for receiving input events from a sequencer. The methods offered by this
@@ -231,14 +371,44 @@ impl<'a> Input<'a> {
         acheck!(snd_seq_set_input_buffer_size((self.0).0, size as size_t)).map(|_| ())
     }
 
+    /// Removes all events from the input buffer and the kernel's input queue.
+    pub fn drop_input(&self) -> Result<()> {
+        acheck!(snd_seq_drop_input((self.0).0)).map(|_| ())
+    }
+
+    /// Removes all events from the input buffer, but not those still in the kernel.
+    pub fn drop_input_buffer(&self) -> Result<()> {
+        acheck!(snd_seq_drop_input_buffer((self.0).0)).map(|_| ())
+    }
+
+    /// Removes events matching `r`, from the input buffer and the kernel's input queue as well as from
+    /// the output side, depending on `r`.
+    pub fn remove_events(&self, r: &RemoveEvents) -> Result<()> {
+        acheck!(snd_seq_remove_events((self.0).0, r.0)).map(|_| ())
+    }
+
     pub fn stream<'b>(&'b mut self) -> InputStream<'a, 'b> {
-        InputStream(self)
+        InputStream(self, poll::ReadyWaker::new())
     }
 }
 
 
-pub struct InputStream<'a, 'b>(&'b mut Input<'a>) where 'a: 'b;
+/// A `futures::Stream` of events, see `Input::stream`
+pub struct InputStream<'a, 'b>(&'b mut Input<'a>, poll::ReadyWaker) where 'a: 'b;
 
+fn poll_input<'a>(input: &mut Input<'a>, w: &mut poll::ReadyWaker, cx: &mut Context) -> Result<Async<Option<Event<'a>>>> {
+    loop {
+        // Don't fetch from the sequencer here, as that would block in blocking mode
+        if try!(input.event_input_pending(false)) == 0 &&
+            !try!(w.ready_or_wake(&(input.0, Some(Direction::Capture)), cx)) { return Ok(Async::Pending) }
+        match unsafe { input.event_input_unsafe() } {
+            Ok(e) => return Ok(Async::Ready(Some(e))),
+            // Nothing to read after all, so wait for the next event
+            Err(ref e) if e.would_block() => {},
+            Err(e) => return Err(e),
+        }
+    }
+}
 
 impl<'a, 'b> Stream for InputStream<'a, 'b> where 'a: 'b
 {
@@ -248,15 +418,32 @@ impl<'a, 'b> Stream for InputStream<'a, 'b> where 'a: 'b
     fn poll_next(&mut self, cx: &mut Context)
         -> Result<Async<Option<Self::Item>>>
     {
-        if self.0.event_input_pending(true)? == 0 {
-            Ok(Async::Pending)
-        } else {
-            unsafe{self.0.event_input_unsafe()}
-              .map(Some).map(Async::Ready)
-        }
+        poll_input(self.0, &mut self.1, cx)
     }
 }
 
+/// A `futures::Stream` of events that owns its `Seq`, see `Seq::into_stream`
+///
+/// Since the events are copied out of the input buffer, this stream is `'static`, and can
+/// be spawned onto an executor.
+// The waker is dropped first, so that its thread stops polling before the Seq is closed
+pub struct OwnedInputStream(poll::ReadyWaker, Seq);
+
+impl OwnedInputStream {
+    /// The sequencer, e g, for creating ports or sending events.
+    pub fn get_ref(&self) -> &Seq { &self.1 }
+    pub fn into_inner(self) -> Seq { self.1 }
+}
+
+impl Stream for OwnedInputStream {
+    type Item = Event<'static>;
+    type Error = Error;
+
+    fn poll_next(&mut self, cx: &mut Context) -> Result<Async<Option<Event<'static>>>> {
+        let mut input = self.1.input();
+        poll_input(&mut input, &mut self.0, cx).map(|a| a.map(|o| o.map(|e| e.into_owned())))
+    }
+}
 
 fn polldir(o: Option<Direction>) -> c_short {
     match o {
@@ -284,6 +471,11 @@ impl<'a> poll::PollDescriptors for (&'a Seq, Option<Direction>) {
     }
 }
 
+impl poll::EventSource for Seq {
+    fn fds(&self) -> Result<Vec<(RawFd, poll::PollFlags)>> { poll::EventSource::fds(&(self, None)) }
+    fn translate(&self, revents: &[poll::PollFlags]) -> Result<poll::PollFlags> { poll::EventSource::translate(&(self, None), revents) }
+}
+
 /// [snd_seq_client_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_client.html) wrapper
 pub struct ClientInfo(*mut alsa::snd_seq_client_info_t);
 
@@ -310,10 +502,62 @@ impl ClientInfo {
         unsafe { alsa::snd_seq_client_info_get_client(self.0) as i32 }
     }
 
+    /// Creates a new ClientInfo with all fields set to zero.
+    pub fn empty() -> Result<Self> {
+        let z = try!(Self::new());
+        unsafe { ptr::write_bytes(z.0 as *mut u8, 0, alsa::snd_seq_client_info_sizeof()) };
+        Ok(z)
+    }
+
     pub fn get_name(&self) -> Result<&str> {
         let c = unsafe { alsa::snd_seq_client_info_get_name(self.0) };
         from_const("snd_seq_client_info_get_name", c)
     }
+
+    pub fn set_name(&mut self, name: &CStr) {
+        // Note: get_name returns an interior reference, so this one must take &mut self
+        unsafe { alsa::snd_seq_client_info_set_name(self.0, name.as_ptr()) };
+    }
+
+    pub fn get_type(&self) -> Result<ClientType> {
+        ClientType::from_c_int(unsafe { alsa::snd_seq_client_info_get_type(self.0) } as c_int, "snd_seq_client_info_get_type")
+    }
+
+    /// The card number of a kernel client, or -1. Requires alsa-lib 1.1.5 or later.
+    pub fn get_card(&self) -> i32 { unsafe { snd_seq_client_info_get_card(self.0) as i32 } }
+    /// The process id of a user client, or -1. Requires alsa-lib 1.1.5 or later.
+    pub fn get_pid(&self) -> i32 { unsafe { snd_seq_client_info_get_pid(self.0) as i32 } }
+    pub fn get_num_ports(&self) -> i32 { unsafe { alsa::snd_seq_client_info_get_num_ports(self.0) as i32 } }
+    /// Number of events lost because the input pool was full
+    pub fn get_event_lost(&self) -> i32 { unsafe { alsa::snd_seq_client_info_get_event_lost(self.0) as i32 } }
+    pub fn get_broadcast_filter(&self) -> bool { unsafe { alsa::snd_seq_client_info_get_broadcast_filter(self.0) != 0 } }
+    pub fn get_error_bounce(&self) -> bool { unsafe { alsa::snd_seq_client_info_get_error_bounce(self.0) != 0 } }
+
+    /// If set, broadcast events are not received.
+    pub fn set_broadcast_filter(&self, value: bool) { unsafe { alsa::snd_seq_client_info_set_broadcast_filter(self.0, if value { 1 } else { 0 }) } }
+    /// If set, events that cannot be delivered are bounced back to this client.
+    pub fn set_error_bounce(&self, value: bool) { unsafe { alsa::snd_seq_client_info_set_error_bounce(self.0, if value { 1 } else { 0 }) } }
+
+    /// Returns the event filter as a bitmap indexed by event type.
+    ///
+    /// If no event types are added to the filter, the client receives events of all types.
+    pub fn get_event_filter(&self) -> [u8; 32] {
+        let mut r = [0u8; 32];
+        unsafe { ptr::copy_nonoverlapping(alsa::snd_seq_client_info_get_event_filter(self.0), r.as_mut_ptr(), r.len()) };
+        r
+    }
+
+    pub fn set_event_filter(&self, value: &[u8; 32]) {
+        let mut z = *value;
+        unsafe { alsa::snd_seq_client_info_set_event_filter(self.0, z.as_mut_ptr()) }
+    }
+
+    /// Clears the event filter, i e, the client receives events of all types.
+    pub fn event_filter_clear(&self) { unsafe { alsa::snd_seq_client_info_event_filter_clear(self.0) } }
+    /// Adds an event type to the filter. The client then only receives event types added to the filter.
+    pub fn event_filter_add(&self, t: EventType) { unsafe { alsa::snd_seq_client_info_event_filter_add(self.0, t as c_int) } }
+    pub fn event_filter_del(&self, t: EventType) { unsafe { alsa::snd_seq_client_info_event_filter_del(self.0, t as c_int) } }
+    pub fn event_filter_check(&self, t: EventType) -> bool { unsafe { alsa::snd_seq_client_info_event_filter_check(self.0, t as c_int) != 0 } }
 }
 
 impl fmt::Debug for ClientInfo {
@@ -322,6 +566,85 @@ impl fmt::Debug for ClientInfo {
     }
 }
 
+alsa_enum!(
+    /// [SND_SEQ_xxx_CLIENT](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_client.html) constants
+    ClientType, ALL_CLIENT_TYPES[2],
+
+    User = SND_SEQ_USER_CLIENT,
+    Kernel = SND_SEQ_KERNEL_CLIENT,
+);
+
+/// [snd_seq_client_pool_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_client.html) wrapper
+///
+/// Pool sizes are in number of events.
+pub struct ClientPool(*mut alsa::snd_seq_client_pool_t);
+
+unsafe impl Send for ClientPool {}
+
+impl Drop for ClientPool {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_client_pool_free(self.0) } }
+}
+
+impl ClientPool {
+    fn new() -> Result<Self> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_seq_client_pool_malloc(&mut p)).map(|_| ClientPool(p))
+    }
+
+    pub fn get_client(&self) -> i32 { unsafe { alsa::snd_seq_client_pool_get_client(self.0) as i32 } }
+    pub fn get_output_pool(&self) -> u32 { unsafe { alsa::snd_seq_client_pool_get_output_pool(self.0) as u32 } }
+    pub fn get_input_pool(&self) -> u32 { unsafe { alsa::snd_seq_client_pool_get_input_pool(self.0) as u32 } }
+    pub fn get_output_room(&self) -> u32 { unsafe { alsa::snd_seq_client_pool_get_output_room(self.0) as u32 } }
+    pub fn get_output_free(&self) -> u32 { unsafe { alsa::snd_seq_client_pool_get_output_free(self.0) as u32 } }
+    pub fn get_input_free(&self) -> u32 { unsafe { alsa::snd_seq_client_pool_get_input_free(self.0) as u32 } }
+
+    pub fn set_output_pool(&self, value: u32) { unsafe { alsa::snd_seq_client_pool_set_output_pool(self.0, value as size_t) } }
+    pub fn set_input_pool(&self, value: u32) { unsafe { alsa::snd_seq_client_pool_set_input_pool(self.0, value as size_t) } }
+    /// In blocking mode, writing blocks until this many events are free in the output pool.
+    pub fn set_output_room(&self, value: u32) { unsafe { alsa::snd_seq_client_pool_set_output_room(self.0, value as size_t) } }
+}
+
+impl fmt::Debug for ClientPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ClientPool({}, output {}/{}, input {}/{}, output room {})", self.get_client(), self.get_output_free(),
+            self.get_output_pool(), self.get_input_free(), self.get_input_pool(), self.get_output_room())
+    }
+}
+
+/// [snd_seq_system_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_system.html) wrapper
+pub struct SystemInfo(*mut alsa::snd_seq_system_info_t);
+
+unsafe impl Send for SystemInfo {}
+
+impl Drop for SystemInfo {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_system_info_free(self.0) } }
+}
+
+impl SystemInfo {
+    fn new() -> Result<Self> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_seq_system_info_malloc(&mut p)).map(|_| SystemInfo(p))
+    }
+
+    /// Maximum number of queues
+    pub fn get_queues(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_queues(self.0) as i32 } }
+    /// Maximum number of clients
+    pub fn get_clients(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_clients(self.0) as i32 } }
+    /// Maximum number of ports per client
+    pub fn get_ports(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_ports(self.0) as i32 } }
+    /// Maximum number of channels per port
+    pub fn get_channels(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_channels(self.0) as i32 } }
+    pub fn get_cur_clients(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_cur_clients(self.0) as i32 } }
+    pub fn get_cur_queues(&self) -> i32 { unsafe { alsa::snd_seq_system_info_get_cur_queues(self.0) as i32 } }
+}
+
+impl fmt::Debug for SystemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SystemInfo(clients {}/{}, queues {}/{}, ports {}, channels {})", self.get_cur_clients(), self.get_clients(),
+            self.get_cur_queues(), self.get_queues(), self.get_ports(), self.get_channels())
+    }
+}
+
 #[derive(Copy, Clone)]
 /// Iterates over clients connected to the seq API (both kernel and userspace clients).
 pub struct ClientIter<'a>(&'a Seq, i32);
@@ -559,10 +882,75 @@ impl PortSubscribe {
 
 }
 
+alsa_enum!(
+    /// [SND_SEQ_QUERY_SUBS_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_subscribe.html) constants
+    ///
+    /// `Read` lists the ports a port writes to, `Write` lists the ports it reads from.
+    QuerySubsType, ALL_QUERY_SUBS_TYPES[2],
+
+    Read = SND_SEQ_QUERY_SUBS_READ,
+    Write = SND_SEQ_QUERY_SUBS_WRITE,
+);
+
+/// Iterates over the subscriptions of a port, like `aconnect -l` does.
+///
+/// Each subscription is returned as a `PortSubscribe`, with the queried port either as the sender
+/// (for `QuerySubsType::Read`) or as the destination (for `QuerySubsType::Write`).
+pub struct SubscriptionIter<'a> {
+    seq: &'a Seq,
+    root: Addr,
+    t: QuerySubsType,
+    index: i32,
+}
+
+impl<'a> SubscriptionIter<'a> {
+    pub fn new(seq: &'a Seq, root: Addr, t: QuerySubsType) -> Self {
+        SubscriptionIter { seq: seq, root: root, t: t, index: 0 }
+    }
+}
+
+impl<'a> Iterator for SubscriptionIter<'a> {
+    type Item = PortSubscribe;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut q = ptr::null_mut();
+        if unsafe { alsa::snd_seq_query_subscribe_malloc(&mut q) } < 0 { return None };
+        let root = alsa::snd_seq_addr_t { client: self.root.client as c_uchar, port: self.root.port as c_uchar };
+        let r = unsafe {
+            alsa::snd_seq_query_subscribe_set_root(q, &root);
+            alsa::snd_seq_query_subscribe_set_type(q, self.t as alsa::snd_seq_query_subs_type_t);
+            alsa::snd_seq_query_subscribe_set_index(q, self.index as c_int);
+            alsa::snd_seq_query_port_subscribers((self.seq).0, q)
+        };
+        let z = if r < 0 { None } else { PortSubscribe::empty().ok() };
+        if let Some(ref z) = z { unsafe {
+            let a = &*alsa::snd_seq_query_subscribe_get_addr(q);
+            let a = Addr { client: a.client as i32, port: a.port as i32 };
+            match self.t {
+                QuerySubsType::Read => { z.set_sender(self.root); z.set_dest(a) },
+                QuerySubsType::Write => { z.set_sender(a); z.set_dest(self.root) },
+            }
+            z.set_queue(alsa::snd_seq_query_subscribe_get_queue(q) as i32);
+            z.set_exclusive(alsa::snd_seq_query_subscribe_get_exclusive(q) != 0);
+            z.set_time_update(alsa::snd_seq_query_subscribe_get_time_update(q) != 0);
+            z.set_time_real(alsa::snd_seq_query_subscribe_get_time_real(q) != 0);
+            self.index = alsa::snd_seq_query_subscribe_get_index(q) as i32 + 1;
+        } }
+        unsafe { alsa::snd_seq_query_subscribe_free(q) };
+        z
+    }
+}
+
+impl fmt::Debug for PortSubscribe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PortSubscribe({:?} -> {:?}, queue {}, exclusive: {}, time_update: {}, time_real: {})", self.get_sender(),
+            self.get_dest(), self.get_queue(), self.get_exclusive(), self.get_time_update(), self.get_time_real())
+    }
+}
+
 /// [snd_seq_event_t](http://www.alsa-project.org/alsa-doc/alsa-lib/structsnd__seq__event__t.html) wrapper
 ///
 /// Fields of the event is not directly exposed. Instead call `Event::new` to set data (which can be, e g, an EvNote).
-/// Use `get_type` and `get_data` to retreive data.
+/// Use `get_type` and `get_data` to retreive data, or `get_kind` to `match` on both at once.
 ///
 /// The lifetime parameter refers to the lifetime of an associated external buffer that might be used for
 /// variable-length messages (e.g. SysEx).
@@ -652,6 +1040,72 @@ impl<'a> Event<'a> {
     /// Use `get_ext` instead for events carrying variable-length data.
     pub fn get_data<D: EventData>(&self) -> Option<D> { if D::has_data(self.1) { Some(D::get_data(self)) } else { None } }
 
+    /// Extract event type and data in one go, to be used with `match`.
+    pub fn get_kind<'b>(&'b self) -> EventKind<'b> {
+        // The data types are given by the EventData impls, so these unwraps cannot fail.
+        match self.1 {
+            EventType::System => EventKind::System(self.get_data().unwrap()),
+            EventType::Result => EventKind::Result(self.get_data().unwrap()),
+            EventType::Note => EventKind::Note(self.get_data().unwrap()),
+            EventType::Noteon => EventKind::Noteon(self.get_data().unwrap()),
+            EventType::Noteoff => EventKind::Noteoff(self.get_data().unwrap()),
+            EventType::Keypress => EventKind::Keypress(self.get_data().unwrap()),
+            EventType::Controller => EventKind::Controller(self.get_data().unwrap()),
+            EventType::Pgmchange => EventKind::Pgmchange(self.get_data().unwrap()),
+            EventType::Chanpress => EventKind::Chanpress(self.get_data().unwrap()),
+            EventType::Pitchbend => EventKind::Pitchbend(self.get_data().unwrap()),
+            EventType::Control14 => EventKind::Control14(self.get_data().unwrap()),
+            EventType::Nonregparam => EventKind::Nonregparam(self.get_data().unwrap()),
+            EventType::Regparam => EventKind::Regparam(self.get_data().unwrap()),
+            EventType::Songpos => EventKind::Songpos(self.get_data().unwrap()),
+            EventType::Songsel => EventKind::Songsel(self.get_data().unwrap()),
+            EventType::Qframe => EventKind::Qframe(self.get_data().unwrap()),
+            EventType::Timesign => EventKind::Timesign(self.get_data().unwrap()),
+            EventType::Keysign => EventKind::Keysign(self.get_data().unwrap()),
+            EventType::Start => EventKind::Start(self.get_data().unwrap()),
+            EventType::Continue => EventKind::Continue(self.get_data().unwrap()),
+            EventType::Stop => EventKind::Stop(self.get_data().unwrap()),
+            EventType::SetposTick => EventKind::SetposTick(self.get_data().unwrap()),
+            EventType::SetposTime => EventKind::SetposTime(self.get_data().unwrap()),
+            EventType::Tempo => EventKind::Tempo(self.get_data().unwrap()),
+            EventType::Clock => EventKind::Clock(self.get_data().unwrap()),
+            EventType::Tick => EventKind::Tick(self.get_data().unwrap()),
+            EventType::QueueSkew => EventKind::QueueSkew(self.get_data().unwrap()),
+            EventType::SyncPos => EventKind::SyncPos(self.get_data().unwrap()),
+            EventType::TuneRequest => EventKind::TuneRequest,
+            EventType::Reset => EventKind::Reset,
+            EventType::Sensing => EventKind::Sensing,
+            EventType::Echo => EventKind::Echo(self.get_data().unwrap()),
+            EventType::Oss => EventKind::Oss(self.get_data().unwrap()),
+            EventType::ClientStart => EventKind::ClientStart(self.get_data().unwrap()),
+            EventType::ClientExit => EventKind::ClientExit(self.get_data().unwrap()),
+            EventType::ClientChange => EventKind::ClientChange(self.get_data().unwrap()),
+            EventType::PortStart => EventKind::PortStart(self.get_data().unwrap()),
+            EventType::PortExit => EventKind::PortExit(self.get_data().unwrap()),
+            EventType::PortChange => EventKind::PortChange(self.get_data().unwrap()),
+            EventType::PortSubscribed => EventKind::PortSubscribed(self.get_data().unwrap()),
+            EventType::PortUnsubscribed => EventKind::PortUnsubscribed(self.get_data().unwrap()),
+            EventType::Usr0 => EventKind::Usr(0, self.get_data().unwrap()),
+            EventType::Usr1 => EventKind::Usr(1, self.get_data().unwrap()),
+            EventType::Usr2 => EventKind::Usr(2, self.get_data().unwrap()),
+            EventType::Usr3 => EventKind::Usr(3, self.get_data().unwrap()),
+            EventType::Usr4 => EventKind::Usr(4, self.get_data().unwrap()),
+            EventType::Usr5 => EventKind::Usr(5, self.get_data().unwrap()),
+            EventType::Usr6 => EventKind::Usr(6, self.get_data().unwrap()),
+            EventType::Usr7 => EventKind::Usr(7, self.get_data().unwrap()),
+            EventType::Usr8 => EventKind::Usr(8, self.get_data().unwrap()),
+            EventType::Usr9 => EventKind::Usr(9, self.get_data().unwrap()),
+            EventType::Sysex => EventKind::Sysex(self.get_ext().unwrap()),
+            EventType::Bounce => EventKind::Bounce(self.get_ext().unwrap()),
+            EventType::UsrVar0 => EventKind::UsrVar(0, self.get_ext().unwrap()),
+            EventType::UsrVar1 => EventKind::UsrVar(1, self.get_ext().unwrap()),
+            EventType::UsrVar2 => EventKind::UsrVar(2, self.get_ext().unwrap()),
+            EventType::UsrVar3 => EventKind::UsrVar(3, self.get_ext().unwrap()),
+            EventType::UsrVar4 => EventKind::UsrVar(4, self.get_ext().unwrap()),
+            EventType::None => EventKind::None,
+        }
+    }
+
     /// Extract the variable-length data carried by events of type `Sysex`, `Bounce`, or the `UsrVar` types.
     pub fn get_ext<'b>(&'b self) -> Option<&'b [u8]> {
         if Event::has_ext_data(self.1) {
@@ -871,6 +1325,158 @@ impl EventData for EvCtrl {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// 14-bit controller change, i e, a controller (0 - 31) together with its LSB (32 - 63).
+///
+/// This is an alternative view of `EvCtrl`, for `EventType::Control14` only.
+pub struct EvCtrl14 {
+    pub channel: u8,
+    pub controller: u8,
+    /// 0 - 16383
+    pub value: u16,
+}
+
+impl EventData for EvCtrl14 {
+    fn has_data(e: EventType) -> bool { e == EventType::Control14 }
+    fn get_data(ev: &Event) -> Self {
+         let z = EvCtrl::get_data(ev);
+         EvCtrl14 { channel: z.channel, controller: z.param as u8, value: z.value as u16 }
+    }
+    fn set_data(&self, ev: &mut Event) {
+         EvCtrl { channel: self.channel, param: self.controller as u32, value: self.value as i32 }.set_data(ev)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// Registered (RPN) or non-registered (NRPN) parameter change.
+///
+/// This is an alternative view of `EvCtrl`, for `EventType::Regparam` and `EventType::Nonregparam`.
+pub struct EvParam {
+    pub channel: u8,
+    /// 0 - 16383
+    pub param: u16,
+    /// 0 - 16383
+    pub value: u16,
+}
+
+impl EventData for EvParam {
+    fn has_data(e: EventType) -> bool {
+         match e {
+             EventType::Regparam => true,
+             EventType::Nonregparam => true,
+             _ => false,
+         }
+    }
+    fn get_data(ev: &Event) -> Self {
+         let z = EvCtrl::get_data(ev);
+         EvParam { channel: z.channel, param: z.param as u16, value: z.value as u16 }
+    }
+    fn set_data(&self, ev: &mut Event) {
+         EvCtrl { channel: self.channel, param: self.param as u32, value: self.value as i32 }.set_data(ev)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// Song position pointer, for `EventType::Songpos`.
+pub struct EvSongPos {
+    /// Number of MIDI beats (sixteenth notes) since the start of the song, 0 - 16383
+    pub beats: u16,
+}
+
+impl EventData for EvSongPos {
+    fn has_data(e: EventType) -> bool { e == EventType::Songpos }
+    fn get_data(ev: &Event) -> Self { EvSongPos { beats: EvCtrl::get_data(ev).value as u16 } }
+    fn set_data(&self, ev: &mut Event) { EvCtrl { channel: 0, param: 0, value: self.beats as i32 }.set_data(ev) }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// Song select, for `EventType::Songsel`.
+pub struct EvSongSel {
+    pub song: u8,
+}
+
+impl EventData for EvSongSel {
+    fn has_data(e: EventType) -> bool { e == EventType::Songsel }
+    fn get_data(ev: &Event) -> Self { EvSongSel { song: EvCtrl::get_data(ev).value as u8 } }
+    fn set_data(&self, ev: &mut Event) { EvCtrl { channel: 0, param: 0, value: self.song as i32 }.set_data(ev) }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// MIDI time code quarter frame, for `EventType::Qframe`.
+pub struct EvQFrame {
+    /// Which part of the time code this is, 0 - 7
+    pub message_type: u8,
+    /// 0 - 15
+    pub value: u8,
+}
+
+impl EventData for EvQFrame {
+    fn has_data(e: EventType) -> bool { e == EventType::Qframe }
+    fn get_data(ev: &Event) -> Self {
+         let v = EvCtrl::get_data(ev).value;
+         EvQFrame { message_type: ((v >> 4) & 7) as u8, value: (v & 15) as u8 }
+    }
+    fn set_data(&self, ev: &mut Event) {
+         let v = ((self.message_type as i32 & 7) << 4) | (self.value as i32 & 15);
+         EvCtrl { channel: 0, param: 0, value: v }.set_data(ev)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+/// Time signature, for `EventType::Timesign`.
+///
+/// The fields are the same as in the time signature meta event of a Standard MIDI File, and they
+/// are packed into the event's value in that order, most significant byte first.
+pub struct EvTimeSig {
+    pub numerator: u8,
+    /// The denominator as a power of two, e g, 3 for x/8
+    pub denominator: u8,
+    /// MIDI clocks per metronome click
+    pub clocks_per_click: u8,
+    /// Number of notated 32nd notes per quarter note
+    pub notated_32nds: u8,
+}
+
+impl Default for EvTimeSig {
+    fn default() -> Self { EvTimeSig { numerator: 4, denominator: 2, clocks_per_click: 24, notated_32nds: 8 } }
+}
+
+impl EventData for EvTimeSig {
+    fn has_data(e: EventType) -> bool { e == EventType::Timesign }
+    fn get_data(ev: &Event) -> Self {
+         let v = EvCtrl::get_data(ev).value as u32;
+         EvTimeSig { numerator: (v >> 24) as u8, denominator: (v >> 16) as u8, clocks_per_click: (v >> 8) as u8, notated_32nds: v as u8 }
+    }
+    fn set_data(&self, ev: &mut Event) {
+         let v = ((self.numerator as u32) << 24) | ((self.denominator as u32) << 16) |
+             ((self.clocks_per_click as u32) << 8) | (self.notated_32nds as u32);
+         EvCtrl { channel: 0, param: 0, value: v as i32 }.set_data(ev)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash, Default)]
+/// Key signature, for `EventType::Keysign`.
+///
+/// The fields are the same as in the key signature meta event of a Standard MIDI File, and they
+/// are packed into the event's value in that order, most significant byte first.
+pub struct EvKeySig {
+    /// Number of sharps (positive) or flats (negative), -7 - 7
+    pub sharps: i8,
+    pub minor: bool,
+}
+
+impl EventData for EvKeySig {
+    fn has_data(e: EventType) -> bool { e == EventType::Keysign }
+    fn get_data(ev: &Event) -> Self {
+         let v = EvCtrl::get_data(ev).value;
+         EvKeySig { sharps: (v >> 8) as i8, minor: v & 0xff != 0 }
+    }
+    fn set_data(&self, ev: &mut Event) {
+         let v = ((self.sharps as u8 as i32) << 8) | (if self.minor { 1 } else { 0 });
+         EvCtrl { channel: 0, param: 0, value: v }.set_data(ev)
+    }
+}
+
 impl EventData for Addr {
     fn has_data(e: EventType) -> bool {
          match e {
@@ -1052,6 +1658,117 @@ impl EventData for EvResult {
 
 
 
+/// Event type and data of a received event, see `Event::get_kind`.
+///
+/// Most event families have an `EventData` type of their own; the variants carry the same type
+/// that `Event::get_data` would return for that event type.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EventKind<'a> {
+    System(EvResult),
+    Result(EvResult),
+    Note(EvNote),
+    Noteon(EvNote),
+    Noteoff(EvNote),
+    Keypress(EvNote),
+    Controller(EvCtrl),
+    Pgmchange(EvCtrl),
+    Chanpress(EvCtrl),
+    Pitchbend(EvCtrl),
+    Control14(EvCtrl14),
+    Nonregparam(EvParam),
+    Regparam(EvParam),
+    Songpos(EvSongPos),
+    Songsel(EvSongSel),
+    Qframe(EvQFrame),
+    Timesign(EvTimeSig),
+    Keysign(EvKeySig),
+    Start(EvQueueControl<()>),
+    Continue(EvQueueControl<()>),
+    Stop(EvQueueControl<()>),
+    SetposTick(EvQueueControl<u32>),
+    SetposTime(EvQueueControl<time::Duration>),
+    Tempo(EvQueueControl<i32>),
+    Clock(EvQueueControl<()>),
+    Tick(EvQueueControl<u32>),
+    QueueSkew(EvQueueControl<()>),
+    SyncPos(EvQueueControl<u32>),
+    TuneRequest,
+    Reset,
+    Sensing,
+    Echo([u8; 12]),
+    Oss([u8; 12]),
+    ClientStart(Addr),
+    ClientExit(Addr),
+    ClientChange(Addr),
+    PortStart(Addr),
+    PortExit(Addr),
+    PortChange(Addr),
+    PortSubscribed(Connect),
+    PortUnsubscribed(Connect),
+    /// `EventType::Usr0` to `Usr9`
+    Usr(u8, [u8; 12]),
+    Sysex(&'a [u8]),
+    Bounce(&'a [u8]),
+    /// `EventType::UsrVar0` to `UsrVar4`
+    UsrVar(u8, &'a [u8]),
+    None,
+}
+
+impl<'a> EventKind<'a> {
+    pub fn get_type(&self) -> EventType {
+        match *self {
+            EventKind::System(_) => EventType::System,
+            EventKind::Result(_) => EventType::Result,
+            EventKind::Note(_) => EventType::Note,
+            EventKind::Noteon(_) => EventType::Noteon,
+            EventKind::Noteoff(_) => EventType::Noteoff,
+            EventKind::Keypress(_) => EventType::Keypress,
+            EventKind::Controller(_) => EventType::Controller,
+            EventKind::Pgmchange(_) => EventType::Pgmchange,
+            EventKind::Chanpress(_) => EventType::Chanpress,
+            EventKind::Pitchbend(_) => EventType::Pitchbend,
+            EventKind::Control14(_) => EventType::Control14,
+            EventKind::Nonregparam(_) => EventType::Nonregparam,
+            EventKind::Regparam(_) => EventType::Regparam,
+            EventKind::Songpos(_) => EventType::Songpos,
+            EventKind::Songsel(_) => EventType::Songsel,
+            EventKind::Qframe(_) => EventType::Qframe,
+            EventKind::Timesign(_) => EventType::Timesign,
+            EventKind::Keysign(_) => EventType::Keysign,
+            EventKind::Start(_) => EventType::Start,
+            EventKind::Continue(_) => EventType::Continue,
+            EventKind::Stop(_) => EventType::Stop,
+            EventKind::SetposTick(_) => EventType::SetposTick,
+            EventKind::SetposTime(_) => EventType::SetposTime,
+            EventKind::Tempo(_) => EventType::Tempo,
+            EventKind::Clock(_) => EventType::Clock,
+            EventKind::Tick(_) => EventType::Tick,
+            EventKind::QueueSkew(_) => EventType::QueueSkew,
+            EventKind::SyncPos(_) => EventType::SyncPos,
+            EventKind::TuneRequest => EventType::TuneRequest,
+            EventKind::Reset => EventType::Reset,
+            EventKind::Sensing => EventType::Sensing,
+            EventKind::Echo(_) => EventType::Echo,
+            EventKind::Oss(_) => EventType::Oss,
+            EventKind::ClientStart(_) => EventType::ClientStart,
+            EventKind::ClientExit(_) => EventType::ClientExit,
+            EventKind::ClientChange(_) => EventType::ClientChange,
+            EventKind::PortStart(_) => EventType::PortStart,
+            EventKind::PortExit(_) => EventType::PortExit,
+            EventKind::PortChange(_) => EventType::PortChange,
+            EventKind::PortSubscribed(_) => EventType::PortSubscribed,
+            EventKind::PortUnsubscribed(_) => EventType::PortUnsubscribed,
+            EventKind::Usr(n, _) => [EventType::Usr0, EventType::Usr1, EventType::Usr2, EventType::Usr3, EventType::Usr4,
+                EventType::Usr5, EventType::Usr6, EventType::Usr7, EventType::Usr8, EventType::Usr9][n as usize],
+            EventKind::Sysex(_) => EventType::Sysex,
+            EventKind::Bounce(_) => EventType::Bounce,
+            EventKind::UsrVar(n, _) => [EventType::UsrVar0, EventType::UsrVar1, EventType::UsrVar2, EventType::UsrVar3,
+                EventType::UsrVar4][n as usize],
+            EventKind::None => EventType::None,
+        }
+    }
+}
+
 alsa_enum!(
     /// [SND_SEQ_EVENT_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_events.html) constants
 
@@ -1153,6 +1870,282 @@ impl QueueTempo {
     pub fn set_skew_base(&self, value: u32) { unsafe { alsa::snd_seq_queue_tempo_set_skew_base(self.0, value as c_uint) } }
 }
 
+/// [snd_seq_queue_status_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_queue.html) wrapper
+pub struct QueueStatus(*mut alsa::snd_seq_queue_status_t);
+
+unsafe impl Send for QueueStatus {}
+
+impl Drop for QueueStatus {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_queue_status_free(self.0) } }
+}
+
+impl QueueStatus {
+    fn new() -> Result<Self> {
+        let mut q = ptr::null_mut();
+        acheck!(snd_seq_queue_status_malloc(&mut q)).map(|_| QueueStatus(q))
+    }
+
+    pub fn get_queue(&self) -> i32 { unsafe { alsa::snd_seq_queue_status_get_queue(self.0) as i32 } }
+    /// Number of events scheduled on the queue
+    pub fn get_events(&self) -> i32 { unsafe { alsa::snd_seq_queue_status_get_events(self.0) as i32 } }
+    pub fn get_tick_time(&self) -> u32 { unsafe { alsa::snd_seq_queue_status_get_tick_time(self.0) as u32 } }
+    pub fn get_real_time(&self) -> time::Duration { unsafe {
+        let t = &*alsa::snd_seq_queue_status_get_real_time(self.0);
+        time::Duration::new(t.tv_sec as u64, t.tv_nsec as u32)
+    } }
+    pub fn get_status(&self) -> u32 { unsafe { alsa::snd_seq_queue_status_get_status(self.0) as u32 } }
+    pub fn is_running(&self) -> bool { self.get_status() & 1 != 0 }
+}
+
+impl fmt::Debug for QueueStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "QueueStatus({}: tick {}, time {:?}, {} events, running: {})", self.get_queue(), self.get_tick_time(),
+            self.get_real_time(), self.get_events(), self.is_running())
+    }
+}
+
+/// [snd_seq_queue_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_queue.html) wrapper
+pub struct QueueInfo(*mut alsa::snd_seq_queue_info_t);
+
+unsafe impl Send for QueueInfo {}
+
+impl Drop for QueueInfo {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_queue_info_free(self.0) } }
+}
+
+impl QueueInfo {
+    fn new() -> Result<Self> {
+        let mut q = ptr::null_mut();
+        acheck!(snd_seq_queue_info_malloc(&mut q)).map(|_| QueueInfo(q))
+    }
+
+    /// Creates a new QueueInfo with all fields set to zero.
+    pub fn empty() -> Result<Self> {
+        let q = try!(QueueInfo::new());
+        unsafe { ptr::write_bytes(q.0 as *mut u8, 0, alsa::snd_seq_queue_info_sizeof()) };
+        Ok(q)
+    }
+
+    pub fn get_queue(&self) -> i32 { unsafe { alsa::snd_seq_queue_info_get_queue(self.0) as i32 } }
+    pub fn get_name(&self) -> Result<&str> {
+        let c = unsafe { alsa::snd_seq_queue_info_get_name(self.0) };
+        from_const("snd_seq_queue_info_get_name", c)
+    }
+    pub fn get_owner(&self) -> i32 { unsafe { alsa::snd_seq_queue_info_get_owner(self.0) as i32 } }
+    pub fn get_locked(&self) -> bool { unsafe { alsa::snd_seq_queue_info_get_locked(self.0) != 0 } }
+    pub fn get_flags(&self) -> u32 { unsafe { alsa::snd_seq_queue_info_get_flags(self.0) as u32 } }
+
+    pub fn set_name(&mut self, name: &CStr) {
+        // Note: get_name returns an interior reference, so this one must take &mut self
+        unsafe { alsa::snd_seq_queue_info_set_name(self.0, name.as_ptr()) }
+    }
+    pub fn set_owner(&self, value: i32) { unsafe { alsa::snd_seq_queue_info_set_owner(self.0, value as c_int) } }
+    /// If locked, only the owner can control the queue.
+    pub fn set_locked(&self, value: bool) { unsafe { alsa::snd_seq_queue_info_set_locked(self.0, if value { 1 } else { 0 }) } }
+    pub fn set_flags(&self, value: u32) { unsafe { alsa::snd_seq_queue_info_set_flags(self.0, value as c_uint) } }
+}
+
+impl fmt::Debug for QueueInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "QueueInfo({},{:?}, owner {}, locked: {})", self.get_queue(), self.get_name(), self.get_owner(), self.get_locked())
+    }
+}
+
+alsa_enum!(
+    /// [SND_SEQ_TIMER_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_queue.html) constants
+    QueueTimerType, ALL_QUEUE_TIMER_TYPES[3],
+
+    Alsa = SND_SEQ_TIMER_ALSA,
+    MidiClock = SND_SEQ_TIMER_MIDI_CLOCK,
+    MidiTick = SND_SEQ_TIMER_MIDI_TICK,
+);
+
+/// [snd_seq_queue_timer_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_queue.html) wrapper
+///
+/// # Example
+/// Run queue `q` from the high resolution timer instead of the system timer.
+///
+/// ```no_run
+/// # let s = alsa::Seq::open(None, None, false).unwrap();
+/// # let q = s.alloc_queue().unwrap();
+/// use alsa::timer::{Id, GLOBAL_HRTIMER};
+/// let t = s.get_queue_timer(q).unwrap();
+/// t.set_id(&Id::global(GLOBAL_HRTIMER).unwrap());
+/// s.set_queue_timer(q, &t).unwrap();
+/// ```
+pub struct QueueTimer(*mut alsa::snd_seq_queue_timer_t);
+
+unsafe impl Send for QueueTimer {}
+
+impl Drop for QueueTimer {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_queue_timer_free(self.0) } }
+}
+
+impl QueueTimer {
+    fn new() -> Result<Self> {
+        let mut q = ptr::null_mut();
+        acheck!(snd_seq_queue_timer_malloc(&mut q)).map(|_| QueueTimer(q))
+    }
+
+    /// Creates a new QueueTimer with all fields set to zero.
+    pub fn empty() -> Result<Self> {
+        let q = try!(QueueTimer::new());
+        unsafe { ptr::write_bytes(q.0 as *mut u8, 0, alsa::snd_seq_queue_timer_sizeof()) };
+        Ok(q)
+    }
+
+    pub fn get_queue(&self) -> i32 { unsafe { alsa::snd_seq_queue_timer_get_queue(self.0) as i32 } }
+    pub fn get_type(&self) -> Result<QueueTimerType> {
+        QueueTimerType::from_c_int(unsafe { alsa::snd_seq_queue_timer_get_type(self.0) } as c_int, "snd_seq_queue_timer_get_type")
+    }
+    /// The ALSA timer used, for timers of type `QueueTimerType::Alsa`
+    pub fn get_id(&self) -> Result<timer::Id> {
+        let id = try!(timer::Id::new());
+        unsafe { alsa::snd_timer_id_copy(timer::id_ptr(&id), alsa::snd_seq_queue_timer_get_id(self.0)) };
+        Ok(id)
+    }
+    pub fn get_resolution(&self) -> u32 { unsafe { alsa::snd_seq_queue_timer_get_resolution(self.0) as u32 } }
+
+    pub fn set_type(&self, value: QueueTimerType) { unsafe { alsa::snd_seq_queue_timer_set_type(self.0, value as alsa::snd_seq_queue_timer_type_t) } }
+    pub fn set_id(&self, value: &timer::Id) { unsafe { alsa::snd_seq_queue_timer_set_id(self.0, timer::id_ptr(value)) } }
+    pub fn set_resolution(&self, value: u32) { unsafe { alsa::snd_seq_queue_timer_set_resolution(self.0, value as c_uint) } }
+}
+
+impl fmt::Debug for QueueTimer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "QueueTimer({},{:?},{:?}, resolution {})", self.get_queue(), self.get_type(), self.get_id(), self.get_resolution())
+    }
+}
+
+/// [snd_seq_remove_events_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___seq_event.html) wrapper
+///
+/// Selects events for `Seq::remove_events` (or `Input::remove_events`, to remove input events). An event is removed if it matches all conditions that are set.
+///
+/// # Example
+/// Cancel all scheduled events for channel 0 of `dest`, except note offs, so no notes are left hanging.
+///
+/// ```no_run
+/// # use alsa::seq::{Addr, RemoveEvents};
+/// # let s = alsa::Seq::open(None, None, false).unwrap();
+/// # let (q, dest) = (s.alloc_queue().unwrap(), Addr { client: 128, port: 0 });
+/// let r = RemoveEvents::new().unwrap();
+/// r.set_output(true);
+/// r.set_queue(q);
+/// r.set_dest(dest);
+/// r.set_channel(0);
+/// r.set_ignore_off(true);
+/// s.remove_events(&r).unwrap();
+/// ```
+pub struct RemoveEvents(*mut alsa::snd_seq_remove_events_t);
+
+unsafe impl Send for RemoveEvents {}
+
+impl Drop for RemoveEvents {
+    fn drop(&mut self) { unsafe { alsa::snd_seq_remove_events_free(self.0) } }
+}
+
+impl RemoveEvents {
+    /// Creates a new RemoveEvents with no conditions set.
+    pub fn new() -> Result<Self> {
+        let mut q = ptr::null_mut();
+        try!(acheck!(snd_seq_remove_events_malloc(&mut q)));
+        unsafe { ptr::write_bytes(q as *mut u8, 0, alsa::snd_seq_remove_events_sizeof()) };
+        Ok(RemoveEvents(q))
+    }
+
+    fn set_flag(&self, flag: c_uint, value: bool) { unsafe {
+        let c = alsa::snd_seq_remove_events_get_condition(self.0);
+        alsa::snd_seq_remove_events_set_condition(self.0, if value { c | flag } else { c & !flag });
+    } }
+
+    fn get_flag(&self, flag: c_uint) -> bool { unsafe { alsa::snd_seq_remove_events_get_condition(self.0) & flag != 0 } }
+
+    fn set_timestamp(&self, flags: c_uint, t: alsa::snd_seq_timestamp_t) {
+        self.set_flag(SND_SEQ_REMOVE_TIME_BEFORE | SND_SEQ_REMOVE_TIME_AFTER | SND_SEQ_REMOVE_TIME_TICK, false);
+        self.set_flag(flags, true);
+        unsafe { alsa::snd_seq_remove_events_set_time(self.0, &t) };
+    }
+
+    /// Remove events from the input buffer and the kernel's input queue.
+    pub fn set_input(&self, value: bool) { self.set_flag(SND_SEQ_REMOVE_INPUT, value) }
+    /// Remove events from the output buffer and the kernel's output queues.
+    pub fn set_output(&self, value: bool) { self.set_flag(SND_SEQ_REMOVE_OUTPUT, value) }
+
+    /// Only remove events sent to `value`.
+    pub fn set_dest(&self, value: Addr) {
+        let z = alsa::snd_seq_addr_t { client: value.client as c_uchar, port: value.port as c_uchar };
+        unsafe { alsa::snd_seq_remove_events_set_dest(self.0, &z) };
+        self.set_flag(SND_SEQ_REMOVE_DEST, true);
+    }
+
+    /// Only remove events scheduled on queue `value`. The kernel only looks at this when `set_dest` is also used.
+    pub fn set_queue(&self, value: i32) { unsafe { alsa::snd_seq_remove_events_set_queue(self.0, value as c_int) } }
+
+    /// Only remove channel events (notes, controllers etc) on MIDI channel `value`.
+    pub fn set_channel(&self, value: u8) {
+        unsafe { alsa::snd_seq_remove_events_set_channel(self.0, value as c_int) };
+        self.set_flag(SND_SEQ_REMOVE_DEST_CHANNEL, true);
+    }
+
+    /// Only remove events with tag `value`, see `Event::set_tag`.
+    pub fn set_tag(&self, value: u8) {
+        unsafe { alsa::snd_seq_remove_events_set_tag(self.0, value as c_int) };
+        self.set_flag(SND_SEQ_REMOVE_TAG_MATCH, true);
+    }
+
+    /// Only remove events of type `value`.
+    pub fn set_event_type(&self, value: EventType) {
+        unsafe { alsa::snd_seq_remove_events_set_event_type(self.0, value as c_int) };
+        self.set_flag(SND_SEQ_REMOVE_EVENT_TYPE, true);
+    }
+
+    /// Do not remove note off events.
+    pub fn set_ignore_off(&self, value: bool) { self.set_flag(SND_SEQ_REMOVE_IGNORE_OFF, value) }
+
+    /// Only remove events scheduled before tick `value`.
+    ///
+    /// There is only one time condition, so this replaces any earlier call to the `set_time_*` functions.
+    pub fn set_time_before_tick(&self, value: u32) {
+        self.set_timestamp(SND_SEQ_REMOVE_TIME_BEFORE | SND_SEQ_REMOVE_TIME_TICK, alsa::snd_seq_timestamp_t { data: [value, 0] })
+    }
+
+    /// Only remove events scheduled at or after tick `value`.
+    pub fn set_time_after_tick(&self, value: u32) {
+        self.set_timestamp(SND_SEQ_REMOVE_TIME_AFTER | SND_SEQ_REMOVE_TIME_TICK, alsa::snd_seq_timestamp_t { data: [value, 0] })
+    }
+
+    /// Only remove events scheduled before real time `value`.
+    pub fn set_time_before(&self, value: time::Duration) {
+        self.set_timestamp(SND_SEQ_REMOVE_TIME_BEFORE, alsa::snd_seq_timestamp_t { data: [value.as_secs() as u32, value.subsec_nanos()] })
+    }
+
+    /// Only remove events scheduled at or after real time `value`.
+    pub fn set_time_after(&self, value: time::Duration) {
+        self.set_timestamp(SND_SEQ_REMOVE_TIME_AFTER, alsa::snd_seq_timestamp_t { data: [value.as_secs() as u32, value.subsec_nanos()] })
+    }
+
+    pub fn get_queue(&self) -> i32 { unsafe { alsa::snd_seq_remove_events_get_queue(self.0) as i32 } }
+    pub fn get_dest(&self) -> Option<Addr> {
+        if self.get_flag(SND_SEQ_REMOVE_DEST) { unsafe {
+            let z = &*alsa::snd_seq_remove_events_get_dest(self.0);
+            Some(Addr { client: z.client as i32, port: z.port as i32 })
+        } } else { None }
+    }
+    pub fn get_channel(&self) -> Option<u8> {
+        if self.get_flag(SND_SEQ_REMOVE_DEST_CHANNEL) { Some(unsafe { alsa::snd_seq_remove_events_get_channel(self.0) as u8 }) } else { None }
+    }
+    pub fn get_tag(&self) -> Option<u8> {
+        if self.get_flag(SND_SEQ_REMOVE_TAG_MATCH) { Some(unsafe { alsa::snd_seq_remove_events_get_tag(self.0) as u8 }) } else { None }
+    }
+    pub fn get_event_type(&self) -> Result<Option<EventType>> {
+        if !self.get_flag(SND_SEQ_REMOVE_EVENT_TYPE) { return Ok(None) };
+        EventType::from_c_int(unsafe { alsa::snd_seq_remove_events_get_event_type(self.0) }, "snd_seq_remove_events_get_event_type").map(Some)
+    }
+    pub fn get_input(&self) -> bool { self.get_flag(SND_SEQ_REMOVE_INPUT) }
+    pub fn get_output(&self) -> bool { self.get_flag(SND_SEQ_REMOVE_OUTPUT) }
+    pub fn get_ignore_off(&self) -> bool { self.get_flag(SND_SEQ_REMOVE_IGNORE_OFF) }
+}
+
 /// [snd_midi_event_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___m_i_d_i___event.html) Wrapper
 ///
 /// Sequencer event <-> MIDI byte stream coder
@@ -1209,6 +2202,42 @@ fn print_seqs() {
     }
 }
 
+#[test]
+fn seq_client_info() {
+    use std::ffi::CString;
+    let s = super::Seq::open(None, None, false).unwrap();
+    println!("{:?}", s.system_info().unwrap());
+    assert!(s.system_info().unwrap().get_clients() > 0);
+
+    let mut c = s.get_client_info().unwrap();
+    assert_eq!(c.get_client(), s.client_id().unwrap());
+    assert_eq!(c.get_type().unwrap(), ClientType::User);
+    assert_eq!(c.get_num_ports(), 0);
+    c.set_name(&CString::new("rust_test_seq_client_info").unwrap());
+    c.set_error_bounce(true);
+    c.event_filter_add(EventType::Noteon);
+    assert!(c.event_filter_check(EventType::Noteon));
+    assert!(!c.event_filter_check(EventType::Noteoff));
+    s.set_client_info(&c).unwrap();
+
+    let info = PortInfo::empty().unwrap();
+    s.create_port(&info).unwrap();
+    let c = s.get_any_client_info(s.client_id().unwrap()).unwrap();
+    println!("{:?}, card {}, pid {}", c, c.get_card(), c.get_pid());
+    assert_eq!(c.get_name().unwrap(), "rust_test_seq_client_info");
+    assert!(c.get_error_bounce());
+    assert_eq!(c.get_num_ports(), 1);
+    assert!(c.event_filter_check(EventType::Noteon));
+    assert_eq!(c.get_event_filter()[EventType::Noteon as usize / 8], 1 << (EventType::Noteon as usize % 8));
+
+    let p = s.get_client_pool().unwrap();
+    println!("{:?}", p);
+    p.set_output_room(p.get_output_pool() / 4);
+    s.set_client_pool(&p).unwrap();
+    let p2 = s.get_client_pool().unwrap();
+    assert_eq!(p2.get_output_room(), p.get_output_pool() / 4);
+}
+
 #[test]
 fn seq_subscribe() {
     use std::ffi::CString;
@@ -1267,6 +2296,124 @@ fn seq_loopback() {
     assert_eq!(e2.get_data(), Some(note));
 }
 
+#[test]
+fn seq_query_subscribe() {
+    use std::ffi::CString;
+    let s = super::Seq::open(None, None, false).unwrap();
+    s.set_client_name(&CString::new("rust_test_seq_query_subscribe").unwrap()).unwrap();
+    let sinfo = PortInfo::empty().unwrap();
+    sinfo.set_capability(READ | SUBS_READ);
+    s.create_port(&sinfo).unwrap();
+    let dinfo = PortInfo::empty().unwrap();
+    dinfo.set_capability(WRITE | SUBS_WRITE);
+    s.create_port(&dinfo).unwrap();
+    let sender = Addr { client: s.client_id().unwrap(), port: sinfo.get_port() };
+    let dest = Addr { client: s.client_id().unwrap(), port: dinfo.get_port() };
+
+    assert!(s.get_port_subscription(sender, dest).unwrap().is_none());
+    assert_eq!(SubscriptionIter::new(&s, sender, QuerySubsType::Read).count(), 0);
+
+    let subs = PortSubscribe::empty().unwrap();
+    subs.set_sender(sender);
+    subs.set_dest(dest);
+    subs.set_time_update(true);
+    s.subscribe_port(&subs).unwrap();
+
+    let z = s.get_port_subscription(sender, dest).unwrap().unwrap();
+    assert!(z.get_time_update());
+    let r: Vec<_> = SubscriptionIter::new(&s, sender, QuerySubsType::Read).collect();
+    println!("{:?}", r);
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].get_dest(), dest);
+    assert!(r[0].get_time_update());
+    let w: Vec<_> = SubscriptionIter::new(&s, dest, QuerySubsType::Write).collect();
+    assert_eq!(w.len(), 1);
+    assert_eq!(w[0].get_sender(), sender);
+}
+
+#[test]
+fn seq_remove_events() {
+    use std::ffi::CString;
+    let s = super::Seq::open(None, None, false).unwrap();
+    s.set_client_name(&CString::new("rust_test_seq_remove_events").unwrap()).unwrap();
+    let dinfo = PortInfo::empty().unwrap();
+    dinfo.set_capability(WRITE | SUBS_WRITE);
+    s.create_port(&dinfo).unwrap();
+    let dest = Addr { client: s.client_id().unwrap(), port: dinfo.get_port() };
+    let q = s.alloc_queue().unwrap();
+
+    // Schedule a note on each of channel 0 and 1, but don't start the queue
+    for c in 0..2 {
+        let note = EvNote { channel: c, note: 64, duration: 0, velocity: 100, off_velocity: 64 };
+        let mut e = Event::new(EventType::Noteon, &note);
+        e.set_dest(dest);
+        e.schedule_tick(q, false, 1000);
+        s.event_output(&mut e).unwrap();
+    }
+    assert!(s.event_output_pending() > 0);
+    s.drain_output().unwrap();
+    assert_eq!(s.event_output_pending(), 0);
+    assert_eq!(s.get_queue_status(q).unwrap().get_events(), 2);
+
+    let r = RemoveEvents::new().unwrap();
+    r.set_output(true);
+    r.set_queue(q);
+    r.set_dest(dest);
+    r.set_channel(0);
+    r.set_time_after_tick(500);
+    assert_eq!(r.get_channel(), Some(0));
+    assert_eq!(r.get_event_type().unwrap(), None);
+    s.remove_events(&r).unwrap();
+    assert_eq!(s.get_queue_status(q).unwrap().get_events(), 1);
+    r.set_input(true);
+    assert!(s.remove_events(&r).is_err());
+    s.input().remove_events(&r).unwrap();
+
+    s.drop_output().unwrap();
+    assert_eq!(s.get_queue_status(q).unwrap().get_events(), 0);
+    s.set_output_buffer_size(8192).unwrap();
+    assert_eq!(s.get_output_buffer_size(), 8192);
+    s.free_queue(q).unwrap();
+}
+
+#[test]
+fn seq_input_stream() {
+    use std::ffi::CString;
+    use std::{thread, time};
+    use futures::StreamExt;
+    use futures::executor::block_on;
+    assert!(super::Seq::open(None, Some(Direction::Playback), false).unwrap().into_stream().is_err());
+    let s = super::Seq::open(None, Some(Direction::Capture), false).unwrap();
+    s.set_client_name(&CString::new("rust_test_seq_input_stream").unwrap()).unwrap();
+    let dinfo = PortInfo::empty().unwrap();
+    dinfo.set_capability(WRITE | SUBS_WRITE);
+    s.create_port(&dinfo).unwrap();
+    let dest = Addr { client: s.client_id().unwrap(), port: dinfo.get_port() };
+
+    // Send the note after the stream has gone pending, so that waking up is required
+    let t = thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(100));
+        let s2 = super::Seq::open(None, Some(Direction::Playback), false).unwrap();
+        let sinfo = PortInfo::empty().unwrap();
+        sinfo.set_capability(READ);
+        s2.create_port(&sinfo).unwrap();
+        let note = EvNote { channel: 0, note: 64, duration: 0, velocity: 100, off_velocity: 64 };
+        let mut e = Event::new(EventType::Noteon, &note);
+        e.set_source(sinfo.get_port());
+        e.set_dest(dest);
+        e.set_direct();
+        s2.event_output(&mut e).unwrap();
+        s2.drain_output().unwrap();
+    });
+    let stream = s.into_stream().unwrap();
+    let e = match block_on(stream.next()) {
+        Ok((Some(e), _)) => e,
+        _ => panic!("no event received"),
+    };
+    assert_eq!(e.get_data(), Some(EvNote { channel: 0, note: 64, duration: 0, velocity: 100, off_velocity: 64 }));
+    t.join().unwrap();
+}
+
 #[test]
 fn seq_encode_sysex() {
     let mut me = MidiEvent::new(16).unwrap();
@@ -1299,6 +2446,33 @@ fn seq_get_input_twice() {
     let _ = (input1, input2);
 }
 
+#[test]
+fn seq_queue() {
+    use std::ffi::CString;
+    let s = super::Seq::open(None, None, false).unwrap();
+    s.set_client_name(&CString::new("rust_test_seq_queue").unwrap()).unwrap();
+    let name = CString::new("rust_test_queue").unwrap();
+    let q = s.alloc_named_queue(&name).unwrap();
+    assert_eq!(s.query_named_queue(&name).unwrap(), q);
+    assert!(s.get_queue_usage(q).unwrap());
+
+    let info = s.get_queue_info(q).unwrap();
+    assert_eq!(info.get_name().unwrap(), "rust_test_queue");
+    assert_eq!(info.get_owner(), s.client_id().unwrap());
+
+    let t = s.get_queue_timer(q).unwrap();
+    println!("{:?}", t);
+    assert_eq!(t.get_type().unwrap(), QueueTimerType::Alsa);
+    t.set_id(&timer::Id::global(timer::GLOBAL_SYSTEM).unwrap());
+    s.set_queue_timer(q, &t).unwrap();
+
+    let status = s.get_queue_status(q).unwrap();
+    println!("{:?}", status);
+    assert_eq!(status.get_queue(), q);
+    assert!(!status.is_running());
+    s.free_queue(q).unwrap();
+}
+
 #[test]
 fn seq_has_data() {
     for v in EventType::all() {
@@ -1319,3 +2493,57 @@ fn seq_has_data() {
         if i != 1 { panic!("{:?}: {} has_data", v, i) }
     }
 }
+
+#[test]
+fn seq_typed_views() {
+    // The typed views are refinements of EvCtrl, and never overlap each other
+    for &v in EventType::all() {
+        let mut j = 0;
+        for &k in &[EvCtrl14::has_data(v), EvParam::has_data(v), EvSongPos::has_data(v), EvSongSel::has_data(v),
+            EvQFrame::has_data(v), EvTimeSig::has_data(v), EvKeySig::has_data(v)] {
+            if k { j += 1; assert!(EvCtrl::has_data(v), "{:?}", v); }
+        }
+        if j > 1 { panic!("{:?}: {} typed views", v, j) }
+    }
+}
+
+#[test]
+fn seq_event_kind() {
+    for &t in EventType::all() {
+        let e = if Event::has_ext_data(t) { Event::new_ext(t, &[0xf0u8, 0xf7][..]) }
+            else { let mut e = Event(unsafe { mem::zeroed() }, t, None); (e.0)._type = t as c_uchar; e };
+        assert_eq!(e.get_kind().get_type(), t);
+    }
+
+    let e = Event::new(EventType::Noteon, &EvNote { channel: 1, note: 60, velocity: 100, off_velocity: 0, duration: 0 });
+    match e.get_kind() {
+        EventKind::Noteon(n) => assert_eq!(n.note, 60),
+        k => panic!("{:?}", k),
+    }
+    let e = Event::new_ext(EventType::UsrVar3, vec!(1u8, 2, 3));
+    assert_eq!(e.get_kind(), EventKind::UsrVar(3, &[1, 2, 3]));
+}
+
+#[test]
+fn seq_typed_data() {
+    let p = EvParam { channel: 2, param: 0x1234, value: 0x2345 };
+    let e = Event::new(EventType::Nonregparam, &p);
+    assert_eq!(e.get_data(), Some(p));
+    assert_eq!(e.get_data(), Some(EvCtrl { channel: 2, param: 0x1234, value: 0x2345 }));
+    assert_eq!(e.get_data::<EvCtrl14>(), None);
+
+    let c = EvCtrl14 { channel: 15, controller: 7, value: 16383 };
+    assert_eq!(Event::new(EventType::Control14, &c).get_data(), Some(c));
+    let q = EvQFrame { message_type: 7, value: 9 };
+    let e = Event::new(EventType::Qframe, &q);
+    assert_eq!(e.get_data(), Some(q));
+    assert_eq!(e.get_data::<EvCtrl>().unwrap().value, 0x79);
+    let t = EvTimeSig { numerator: 6, denominator: 3, clocks_per_click: 36, notated_32nds: 8 };
+    assert_eq!(Event::new(EventType::Timesign, &t).get_data(), Some(t));
+    let k = EvKeySig { sharps: -3, minor: true };
+    assert_eq!(Event::new(EventType::Keysign, &k).get_kind(), EventKind::Keysign(k));
+    let s = EvSongPos { beats: 1000 };
+    assert_eq!(Event::new(EventType::Songpos, &s).get_data(), Some(s));
+    let s = EvSongSel { song: 5 };
+    assert_eq!(Event::new(EventType::Songsel, &s).get_data(), Some(s));
+}
@@ -0,0 +1,788 @@
+//! Standard MIDI File reading and writing, and playback and recording through the sequencer
+//!
+//! This is roughly what the `aplaymidi` and `arecordmidi` utilities do. Reading and writing
+//! files is done by `Smf`, which does not need a sequencer. `Player` schedules the events of an
+//! `Smf` on a sequencer queue, and `Recorder` builds an `Smf` from events received from the sequencer.
+
+use std::io::{self, Read, Write};
+use std::ffi::CStr;
+use super::error::*;
+use super::seq::{self, Seq, Addr, Event, EventType, EvNote, EvCtrl, EvCtrl14, EvParam, EvTimeSig, EvKeySig,
+    EvQueueControl, PortInfo, PortSubscribe, QueueTempo, RemoveEvents};
+
+/// Meta event type for tempo changes, see `SmfData::Meta`
+pub const META_TEMPO: u8 = 0x51;
+/// Meta event type for the end of a track, see `SmfData::Meta`
+pub const META_END_OF_TRACK: u8 = 0x2f;
+/// Meta event type for the track name, see `SmfData::Meta`
+pub const META_TRACK_NAME: u8 = 0x03;
+/// Meta event type for time signatures, see `SmfData::Meta`
+pub const META_TIME_SIGNATURE: u8 = 0x58;
+/// Meta event type for key signatures, see `SmfData::Meta`
+pub const META_KEY_SIGNATURE: u8 = 0x59;
+
+/// The default tempo of a MIDI file, 120 beats per minute, in microseconds per quarter note
+pub const DEFAULT_TEMPO: u32 = 500000;
+
+/// Standard MIDI File format
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Format {
+    /// Format 0 - a single track
+    Single = 0,
+    /// Format 1 - several tracks played simultaneously
+    Multi = 1,
+}
+
+/// How delta times of a Standard MIDI File are to be interpreted
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Timing {
+    /// Ticks per quarter note
+    Ppq(u16),
+    /// Frames per second (24, 25, 29 for 30 drop frame, or 30) and ticks per frame
+    Smpte(u8, u8),
+}
+
+impl Timing {
+    /// Returns the queue ppq and tempo that make the queue tick at the rate of this timing.
+    ///
+    /// For `Ppq`, the tempo is `DEFAULT_TEMPO`, to be changed by tempo meta events.
+    pub fn queue_tempo(&self) -> (i32, u32) {
+        match *self {
+            Timing::Ppq(ppq) => (ppq as i32, DEFAULT_TEMPO),
+            Timing::Smpte(24, tpf) => (12 * tpf as i32, 500000),
+            Timing::Smpte(25, tpf) => (10 * tpf as i32, 400000),
+            Timing::Smpte(29, tpf) => (2997 * tpf as i32, 100000000),
+            Timing::Smpte(_, tpf) => (15 * tpf as i32, 500000),
+        }
+    }
+
+    fn from_division(d: u16) -> io::Result<Timing> {
+        if d & 0x8000 == 0 { return if d > 0 { Ok(Timing::Ppq(d)) } else { Err(invalid("zero ticks per quarter note")) } };
+        let fps = ((d >> 8) as u8 as i8).wrapping_neg() as u8;
+        match fps {
+            24 | 25 | 29 | 30 => Ok(Timing::Smpte(fps, d as u8)),
+            _ => Err(invalid("invalid SMPTE frames per second")),
+        }
+    }
+
+    fn to_division(&self) -> u16 {
+        match *self {
+            Timing::Ppq(ppq) => ppq & 0x7fff,
+            Timing::Smpte(fps, tpf) => (((-(fps as i8)) as u8 as u16) << 8) | tpf as u16,
+        }
+    }
+}
+
+/// Contents of an event in a Standard MIDI File
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SmfData {
+    /// A channel message, starting with the status byte. Running status is resolved when reading.
+    Midi(Vec<u8>),
+    /// A system exclusive message, including the leading 0xF0 and (normally) the trailing 0xF7
+    Sysex(Vec<u8>),
+    /// Arbitrary bytes to send, the 0xF7 "escape" event
+    Escape(Vec<u8>),
+    /// Meta event type and data, e g, `META_TEMPO`
+    Meta(u8, Vec<u8>),
+}
+
+impl SmfData {
+    /// A tempo meta event, in microseconds per quarter note
+    pub fn tempo(usecs: u32) -> SmfData {
+        SmfData::Meta(META_TEMPO, vec!((usecs >> 16) as u8, (usecs >> 8) as u8, usecs as u8))
+    }
+
+    /// Returns microseconds per quarter note, if this is a tempo meta event
+    pub fn get_tempo(&self) -> Option<u32> {
+        match *self {
+            SmfData::Meta(META_TEMPO, ref d) if d.len() >= 3 => Some(((d[0] as u32) << 16) | ((d[1] as u32) << 8) | d[2] as u32),
+            _ => None,
+        }
+    }
+
+    /// Converts this to a sequencer event, or returns `None` for meta events.
+    ///
+    /// Tempo meta events need the queue to act on, so they are handled by `Player` instead.
+    pub fn to_event<'a>(&'a self) -> Option<Event<'a>> {
+        let d = match *self {
+            SmfData::Midi(ref d) if d.len() >= 2 => d,
+            SmfData::Sysex(ref d) | SmfData::Escape(ref d) if d.len() > 0 => return Some(Event::new_ext(EventType::Sysex, &d[..])),
+            _ => return None,
+        };
+        let channel = d[0] & 0xf;
+        let b = if d.len() >= 3 { d[2] } else { 0 };
+        let note = |t, velocity| Event::new(t, &EvNote { channel: channel, note: d[1], velocity: velocity, off_velocity: 0, duration: 0 });
+        let ctrl = |t, param, value| Event::new(t, &EvCtrl { channel: channel, param: param, value: value });
+        Some(match d[0] & 0xf0 {
+            // alsa-lib puts the release velocity of a note off in the velocity field
+            0x80 => note(EventType::Noteoff, b),
+            0x90 => note(EventType::Noteon, b),
+            0xa0 => note(EventType::Keypress, b),
+            0xb0 => ctrl(EventType::Controller, d[1] as u32, b as i32),
+            0xc0 => ctrl(EventType::Pgmchange, 0, d[1] as i32),
+            0xd0 => ctrl(EventType::Chanpress, 0, d[1] as i32),
+            0xe0 => ctrl(EventType::Pitchbend, 0, (((b as i32) << 7) | d[1] as i32) - 8192),
+            _ => return None,
+        })
+    }
+
+    /// Converts a sequencer event to what a Standard MIDI File would contain.
+    ///
+    /// Some events, e g, `Control14` and `Regparam`, become several controller messages. Events that have
+    /// no representation in a MIDI file, e g, port announcements or `Control14` for controllers above 31,
+    /// result in an empty `Vec`. A `Note` becomes its note on only, as the note off belongs at a later
+    /// time; `Recorder` adds it.
+    pub fn from_event(e: &Event) -> Vec<SmfData> {
+        fn midi(status: u8, channel: u8, d: &[u8]) -> SmfData {
+            let mut v = vec!(status | (channel & 0xf));
+            v.extend(d.iter().map(|&x| x & 0x7f));
+            SmfData::Midi(v)
+        }
+        fn cc(channel: u8, c: u8, value: u16) -> SmfData { midi(0xb0, channel, &[c, value as u8]) }
+        match e.get_type() {
+            EventType::Noteon | EventType::Note => { let n: EvNote = e.get_data().unwrap(); vec!(midi(0x90, n.channel, &[n.note, n.velocity])) },
+            EventType::Noteoff => { let n: EvNote = e.get_data().unwrap(); vec!(midi(0x80, n.channel, &[n.note, n.velocity])) },
+            EventType::Keypress => { let n: EvNote = e.get_data().unwrap(); vec!(midi(0xa0, n.channel, &[n.note, n.velocity])) },
+            EventType::Controller => { let c: EvCtrl = e.get_data().unwrap(); vec!(cc(c.channel, c.param as u8, c.value as u16)) },
+            EventType::Pgmchange => { let c: EvCtrl = e.get_data().unwrap(); vec!(midi(0xc0, c.channel, &[c.value as u8])) },
+            EventType::Chanpress => { let c: EvCtrl = e.get_data().unwrap(); vec!(midi(0xd0, c.channel, &[c.value as u8])) },
+            EventType::Pitchbend => {
+                let c: EvCtrl = e.get_data().unwrap();
+                let v = (c.value + 8192) as u16;
+                vec!(midi(0xe0, c.channel, &[v as u8, (v >> 7) as u8]))
+            },
+            EventType::Control14 => {
+                let c: EvCtrl14 = e.get_data().unwrap();
+                // Controllers 0 - 31 have their LSB at 32 - 63, others have no LSB
+                if c.controller >= 32 { return vec!() };
+                vec!(cc(c.channel, c.controller, c.value >> 7), cc(c.channel, c.controller + 32, c.value))
+            },
+            EventType::Regparam | EventType::Nonregparam => {
+                let p: EvParam = e.get_data().unwrap();
+                let (msb, lsb) = if e.get_type() == EventType::Regparam { (101, 100) } else { (99, 98) };
+                vec!(cc(p.channel, msb, p.param >> 7), cc(p.channel, lsb, p.param), cc(p.channel, 6, p.value >> 7), cc(p.channel, 38, p.value))
+            },
+            EventType::Timesign => {
+                let t: EvTimeSig = e.get_data().unwrap();
+                vec!(SmfData::Meta(META_TIME_SIGNATURE, vec!(t.numerator, t.denominator, t.clocks_per_click, t.notated_32nds)))
+            },
+            EventType::Keysign => {
+                let k: EvKeySig = e.get_data().unwrap();
+                vec!(SmfData::Meta(META_KEY_SIGNATURE, vec!(k.sharps as u8, if k.minor { 1 } else { 0 })))
+            },
+            EventType::Sysex => {
+                let d = e.get_ext().unwrap();
+                if d.first() == Some(&0xf0) { vec!(SmfData::Sysex(d.into())) } else { vec!(SmfData::Escape(d.into())) }
+            },
+            _ => vec!(),
+        }
+    }
+}
+
+/// An event in a track, at an absolute time
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SmfEvent {
+    /// Time in ticks since the start of the track
+    pub tick: u32,
+    pub data: SmfData,
+}
+
+/// A track (MTrk chunk) of a Standard MIDI File. The events must be sorted by time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Track {
+    pub events: Vec<SmfEvent>,
+}
+
+impl Track {
+    pub fn new() -> Track { Track { events: vec!() } }
+
+    /// Adds an event to the end of the track.
+    pub fn push(&mut self, tick: u32, data: SmfData) { self.events.push(SmfEvent { tick: tick, data: data }) }
+
+    /// Adds an event after all events at the same or an earlier time, keeping the track sorted.
+    pub fn insert(&mut self, tick: u32, data: SmfData) {
+        let i = self.events.iter().rposition(|e| e.tick <= tick).map(|i| i + 1).unwrap_or(0);
+        self.events.insert(i, SmfEvent { tick: tick, data: data })
+    }
+
+    /// Time of the last event
+    pub fn len_ticks(&self) -> u32 { self.events.last().map(|e| e.tick).unwrap_or(0) }
+}
+
+/// A Standard MIDI File, format 0 or 1
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Smf {
+    pub format: Format,
+    pub timing: Timing,
+    pub tracks: Vec<Track>,
+}
+
+fn invalid(s: &'static str) -> io::Error { io::Error::new(io::ErrorKind::InvalidData, s) }
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < n { return Err(invalid("unexpected end of file")) }
+        let (a, b) = self.0.split_at(n);
+        self.0 = b;
+        Ok(a)
+    }
+    fn u8(&mut self) -> io::Result<u8> { self.bytes(1).map(|b| b[0]) }
+    fn u16(&mut self) -> io::Result<u16> { self.bytes(2).map(|b| ((b[0] as u16) << 8) | b[1] as u16) }
+    fn u32(&mut self) -> io::Result<u32> { self.bytes(4).map(|b| b.iter().fold(0, |acc, &x| (acc << 8) | x as u32)) }
+    fn var(&mut self) -> io::Result<u32> {
+        let mut r = 0u32;
+        for _ in 0..4 {
+            let b = try!(self.u8());
+            r = (r << 7) | (b & 0x7f) as u32;
+            if b & 0x80 == 0 { return Ok(r) }
+        }
+        Err(invalid("variable length quantity too long"))
+    }
+}
+
+fn write_var(v: &mut Vec<u8>, x: u32) -> io::Result<()> {
+    if x >= 1 << 28 { return Err(io::Error::new(io::ErrorKind::InvalidInput, "value too large for a variable length quantity")) };
+    let mut shift = 21;
+    while shift > 0 && (x >> shift) == 0 { shift -= 7 }
+    while shift > 0 { v.push(((x >> shift) & 0x7f) as u8 | 0x80); shift -= 7 }
+    v.push((x & 0x7f) as u8);
+    Ok(())
+}
+
+fn parse_track(mut r: Reader) -> io::Result<Track> {
+    let mut t = Track::new();
+    let mut tick = 0u32;
+    let mut running = 0u8;
+    while r.0.len() > 0 {
+        tick = tick.saturating_add(try!(r.var()));
+        let mut status = try!(r.u8());
+        let data = match status {
+            0xff => {
+                let m = try!(r.u8());
+                let len = try!(r.var()) as usize;
+                let d = try!(r.bytes(len)).to_vec();
+                if m == META_END_OF_TRACK { t.push(tick, SmfData::Meta(m, d)); break };
+                SmfData::Meta(m, d)
+            },
+            0xf0 => {
+                let len = try!(r.var()) as usize;
+                let mut d = vec!(0xf0);
+                d.extend_from_slice(try!(r.bytes(len)));
+                SmfData::Sysex(d)
+            },
+            0xf7 => {
+                let len = try!(r.var()) as usize;
+                SmfData::Escape(try!(r.bytes(len)).to_vec())
+            },
+            _ if status > 0xf0 => return Err(invalid("system message inside track")),
+            _ => {
+                let first = if status < 0x80 {
+                    // Running status, so this was the first data byte
+                    if running == 0 { return Err(invalid("data byte without status")) };
+                    let b = status;
+                    status = running;
+                    b
+                } else { try!(r.u8()) };
+                running = status;
+                let mut d = vec!(status, first);
+                match status & 0xf0 { 0xc0 | 0xd0 => {}, _ => d.push(try!(r.u8())) };
+                SmfData::Midi(d)
+            },
+        };
+        t.push(tick, data);
+    }
+    Ok(t)
+}
+
+impl Smf {
+    pub fn new(format: Format, timing: Timing) -> Smf { Smf { format: format, timing: timing, tracks: vec!() } }
+
+    /// Parses a Standard MIDI File.
+    pub fn parse(data: &[u8]) -> io::Result<Smf> {
+        let mut r = Reader(data);
+        let mut header = None;
+        let mut tracks = vec!();
+        while r.0.len() >= 8 {
+            let id = try!(r.bytes(4));
+            let len = try!(r.u32()) as usize;
+            let mut chunk = Reader(try!(r.bytes(len)));
+            match id {
+                b"MThd" => {
+                    let format = match try!(chunk.u16()) {
+                        0 => Format::Single,
+                        1 => Format::Multi,
+                        _ => return Err(invalid("unsupported MIDI file format")),
+                    };
+                    let ntracks = try!(chunk.u16());
+                    header = Some((format, ntracks, try!(Timing::from_division(try!(chunk.u16())))));
+                },
+                b"MTrk" => {
+                    if header.is_none() { return Err(invalid("track before header")) };
+                    tracks.push(try!(parse_track(chunk)));
+                },
+                _ => {}, // Unknown chunks are to be ignored
+            }
+        }
+        let (format, ntracks, timing) = try!(header.ok_or(invalid("not a Standard MIDI File")));
+        if tracks.len() < ntracks as usize { return Err(invalid("missing tracks")) };
+        Ok(Smf { format: format, timing: timing, tracks: tracks })
+    }
+
+    /// Reads and parses a Standard MIDI File.
+    pub fn read<R: Read>(mut r: R) -> io::Result<Smf> {
+        let mut v = vec!();
+        try!(r.read_to_end(&mut v));
+        Smf::parse(&v)
+    }
+
+    /// Writes the file. An end of track meta event is added to tracks that lack one.
+    ///
+    /// Fails with `InvalidInput` if a delta time or length does not fit in a variable length quantity (28 bits).
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        if self.format == Format::Single && self.tracks.len() != 1 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "format 0 files must have exactly one track"));
+        }
+        let division = self.timing.to_division();
+        try!(w.write_all(&[b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, self.format as u8,
+            (self.tracks.len() >> 8) as u8, self.tracks.len() as u8, (division >> 8) as u8, division as u8]));
+        for t in &self.tracks {
+            let mut v = vec!();
+            let mut tick = 0;
+            let mut ended = false;
+            for e in &t.events {
+                if ended { break };
+                if e.tick < tick { return Err(io::Error::new(io::ErrorKind::InvalidInput, "track events not sorted by time")) };
+                try!(write_var(&mut v, e.tick - tick));
+                tick = e.tick;
+                match e.data {
+                    SmfData::Midi(ref d) => v.extend_from_slice(d),
+                    SmfData::Sysex(ref d) => {
+                        let d = if d.first() == Some(&0xf0) { &d[1..] } else { &d[..] };
+                        v.push(0xf0);
+                        try!(write_var(&mut v, d.len() as u32));
+                        v.extend_from_slice(d);
+                    },
+                    SmfData::Escape(ref d) => { v.push(0xf7); try!(write_var(&mut v, d.len() as u32)); v.extend_from_slice(d) },
+                    SmfData::Meta(m, ref d) => {
+                        v.push(0xff);
+                        v.push(m);
+                        try!(write_var(&mut v, d.len() as u32));
+                        v.extend_from_slice(d);
+                        ended = m == META_END_OF_TRACK;
+                    },
+                }
+            }
+            if !ended { v.extend_from_slice(&[0, 0xff, META_END_OF_TRACK, 0]) };
+            let len = v.len() as u32;
+            try!(w.write_all(&[b'M', b'T', b'r', b'k', (len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]));
+            try!(w.write_all(&v));
+        }
+        Ok(())
+    }
+
+    /// Returns the events of all tracks, merged and sorted by time.
+    ///
+    /// Events at the same time keep the order of the tracks, so that, e g, tempo changes in the first track
+    /// come before notes in other tracks.
+    pub fn merged(&self) -> Vec<&SmfEvent> {
+        let mut v: Vec<_> = self.tracks.iter().flat_map(|t| t.events.iter()).collect();
+        v.sort_by_key(|e| e.tick);
+        v
+    }
+}
+
+/// Plays an `Smf` on a sequencer queue of its own, like `aplaymidi`.
+///
+/// All events are scheduled ahead with `Event::schedule_tick`, and sent from `port` to every
+/// address in `dests`. Tempo meta events are scheduled as tempo changes of the queue.
+///
+/// # Example
+///
+/// ```no_run
+/// use alsa::seq::{self, Addr, PortInfo};
+/// use alsa::smf::{Smf, Player};
+/// use std::ffi::CString;
+/// let smf = Smf::read(std::fs::File::open("song.mid").unwrap()).unwrap();
+/// let s = alsa::Seq::open(None, Some(alsa::Direction::Playback), false).unwrap();
+/// let mut pinfo = PortInfo::empty().unwrap();
+/// pinfo.set_capability(seq::READ);
+/// pinfo.set_name(&CString::new("player").unwrap());
+/// s.create_port(&pinfo).unwrap();
+/// let mut p = Player::new(&s, &smf, pinfo.get_port(), &[Addr { client: 128, port: 0 }]).unwrap();
+/// p.play().unwrap();
+/// p.wait().unwrap();
+/// ```
+pub struct Player<'a> {
+    seq: &'a Seq,
+    queue: i32,
+    port: i32,
+    dests: Vec<Addr>,
+    ppq: i32,
+    tempo: u32,
+    smpte: bool,
+    events: Vec<SmfEvent>,
+    // Index of next event to schedule, and next destination of that event
+    pos: (usize, usize),
+    start_tick: u32,
+    started: bool,
+    running: bool,
+}
+
+impl<'a> Drop for Player<'a> {
+    fn drop(&mut self) { let _ = self.seq.free_queue(self.queue); }
+}
+
+impl<'a> Player<'a> {
+    pub fn new(seq: &'a Seq, smf: &Smf, port: i32, dests: &[Addr]) -> Result<Player<'a>> {
+        let (ppq, tempo) = smf.timing.queue_tempo();
+        let p = Player {
+            seq: seq, queue: try!(seq.alloc_queue()), port: port, dests: dests.into(), ppq: ppq, tempo: tempo,
+            smpte: if let Timing::Smpte(_, _) = smf.timing { true } else { false },
+            events: smf.merged().into_iter().cloned().collect(),
+            pos: (0, 0), start_tick: 0, started: false, running: false,
+        };
+        try!(p.set_tempo(tempo));
+        Ok(p)
+    }
+
+    fn set_tempo(&self, usecs: u32) -> Result<()> {
+        let t = try!(QueueTempo::empty());
+        t.set_ppq(self.ppq);
+        t.set_tempo(usecs);
+        self.seq.set_queue_tempo(self.queue, &t)
+    }
+
+    pub fn get_queue(&self) -> i32 { self.queue }
+
+    /// Time of the last event
+    pub fn len_ticks(&self) -> u32 { self.events.last().map(|e| e.tick).unwrap_or(0) }
+
+    /// Current position of the queue
+    pub fn get_tick(&self) -> Result<u32> { self.seq.get_queue_status(self.queue).map(|s| s.get_tick_time()) }
+
+    /// The tempo in effect at `tick`, in microseconds per quarter note
+    pub fn tempo_at(&self, tick: u32) -> u32 {
+        if self.smpte { return self.tempo };
+        self.events.iter().take_while(|e| e.tick <= tick).filter_map(|e| e.data.get_tempo()).last().unwrap_or(self.tempo)
+    }
+
+    /// Starts (or continues) the queue and schedules the remaining events.
+    ///
+    /// In blocking mode, this blocks while the output pool is full. In nonblocking mode, it returns an error
+    /// that `would_block()` instead; call `play` again later to schedule the rest.
+    pub fn play(&mut self) -> Result<()> {
+        if !self.running {
+            if self.started { try!(self.seq.control_queue(self.queue, EventType::Continue, 0, None)) }
+            else {
+                try!(self.seq.control_queue(self.queue, EventType::Start, 0, None));
+                if self.start_tick > 0 { try!(self.seq.control_queue(self.queue, EventType::SetposTick, self.start_tick as i32, None)) };
+                self.started = true;
+            }
+            self.running = true;
+        }
+        while self.pos.0 < self.events.len() {
+            let e = &self.events[self.pos.0];
+            if let Some(t) = e.data.get_tempo() {
+                if !self.smpte {
+                    let mut ev = Event::new(EventType::Tempo, &EvQueueControl { queue: self.queue, value: t as i32 });
+                    ev.set_source(self.port);
+                    ev.set_dest(Addr::system_timer());
+                    ev.schedule_tick(self.queue, false, e.tick);
+                    try!(self.seq.event_output(&mut ev));
+                }
+            } else if let Some(mut ev) = e.data.to_event() {
+                ev.set_source(self.port);
+                ev.schedule_tick(self.queue, false, e.tick);
+                while self.pos.1 < self.dests.len() {
+                    ev.set_dest(self.dests[self.pos.1]);
+                    try!(self.seq.event_output(&mut ev));
+                    self.pos.1 += 1;
+                }
+            }
+            self.pos = (self.pos.0 + 1, 0);
+        }
+        self.seq.drain_output().map(|_| ())
+    }
+
+    /// Blocks until all scheduled events have been delivered.
+    pub fn wait(&self) -> Result<()> { self.seq.sync_output_queue() }
+
+    /// Stops the queue and removes the events not yet delivered.
+    ///
+    /// To not leave notes hanging, "sustain off" and "all notes off" are then sent on all channels of all destinations.
+    /// A later `play` continues from where the queue stopped.
+    pub fn stop(&mut self) -> Result<()> {
+        try!(self.seq.drop_output_buffer());
+        if self.running {
+            try!(self.seq.control_queue(self.queue, EventType::Stop, 0, None));
+            try!(self.seq.drain_output());
+            self.running = false;
+        }
+        let r = try!(RemoveEvents::new());
+        r.set_output(true);
+        r.set_queue(self.queue);
+        for &d in self.dests.iter().chain(Some(Addr::system_timer()).iter()) {
+            r.set_dest(d);
+            try!(self.seq.remove_events(&r));
+        }
+        for &d in &self.dests {
+            for channel in 0..16 {
+                for &param in &[64, 123] {
+                    let mut ev = Event::new(EventType::Controller, &EvCtrl { channel: channel, param: param, value: 0 });
+                    ev.set_source(self.port);
+                    ev.set_dest(d);
+                    ev.set_direct();
+                    try!(self.seq.event_output(&mut ev));
+                }
+            }
+        }
+        try!(self.seq.drain_output());
+        if self.started {
+            // Everything up to and including the current tick has been delivered
+            let tick = try!(self.get_tick());
+            self.pos = (self.events.iter().position(|e| e.tick > tick).unwrap_or(self.events.len()), 0);
+        }
+        Ok(())
+    }
+
+    /// Moves to `tick`. If playing, playback continues from there.
+    pub fn seek(&mut self, tick: u32) -> Result<()> {
+        let was_running = self.running;
+        try!(self.stop());
+        self.pos = (self.events.iter().position(|e| e.tick >= tick).unwrap_or(self.events.len()), 0);
+        try!(self.set_tempo(self.tempo_at(tick)));
+        if self.started {
+            try!(self.seq.control_queue(self.queue, EventType::SetposTick, tick as i32, None));
+            try!(self.seq.drain_output());
+        } else { self.start_tick = tick };
+        if was_running { self.play() } else { Ok(()) }
+    }
+}
+
+/// Records events into an `Smf`, like `arecordmidi`.
+///
+/// The recorder has a port and a queue of its own, and incoming events are timestamped
+/// against that queue. Events are read by the application, e g with `seq::Input::event_input`,
+/// and handed to `record`. Each source gets a track of its own in the resulting format 1 file.
+pub struct Recorder<'a> {
+    seq: &'a Seq,
+    queue: i32,
+    port: i32,
+    ppq: u16,
+    tempo: u32,
+    sources: Vec<Addr>,
+    tracks: Vec<Track>,
+}
+
+impl<'a> Drop for Recorder<'a> {
+    fn drop(&mut self) {
+        if self.port >= 0 { let _ = self.seq.delete_port(self.port); }
+        let _ = self.seq.free_queue(self.queue);
+    }
+}
+
+impl<'a> Recorder<'a> {
+    /// Creates a port named `name` and starts a queue with the given ppq and tempo (in microseconds per quarter note).
+    pub fn new(seq: &'a Seq, name: &CStr, ppq: u16, tempo: u32) -> Result<Recorder<'a>> {
+        let mut r = Recorder { seq: seq, queue: try!(seq.alloc_queue()), port: -1, ppq: ppq, tempo: tempo, sources: vec!(), tracks: vec!() };
+        let t = try!(QueueTempo::empty());
+        t.set_ppq(ppq as i32);
+        t.set_tempo(tempo);
+        try!(seq.set_queue_tempo(r.queue, &t));
+
+        let mut pinfo = try!(PortInfo::empty());
+        pinfo.set_capability(seq::WRITE | seq::SUBS_WRITE);
+        pinfo.set_type(seq::MIDI_GENERIC | seq::APPLICATION);
+        pinfo.set_name(name);
+        pinfo.set_timestamping(true);
+        pinfo.set_timestamp_queue(r.queue);
+        try!(seq.create_port(&pinfo));
+        r.port = pinfo.get_port();
+
+        try!(seq.control_queue(r.queue, EventType::Start, 0, None));
+        try!(seq.drain_output());
+        Ok(r)
+    }
+
+    pub fn get_port(&self) -> i32 { self.port }
+    pub fn get_queue(&self) -> i32 { self.queue }
+
+    /// Connects `src` to the recorder's port.
+    pub fn connect_from(&mut self, src: Addr) -> Result<()> {
+        let subs = try!(PortSubscribe::empty());
+        subs.set_sender(src);
+        subs.set_dest(Addr { client: try!(self.seq.client_id()), port: self.port });
+        subs.set_queue(self.queue);
+        subs.set_time_update(true);
+        try!(self.seq.subscribe_port(&subs));
+        self.track_for(src);
+        Ok(())
+    }
+
+    fn track_for(&mut self, src: Addr) -> usize {
+        if let Some(i) = self.sources.iter().position(|&a| a == src) { return i };
+        self.sources.push(src);
+        self.tracks.push(Track::new());
+        self.tracks.len() - 1
+    }
+
+    /// Adds an event received on the recorder's port.
+    ///
+    /// A `Note` event is recorded as a note on, and a note off `duration` ticks later.
+    ///
+    /// Returns false if the event was not recorded, because it was not sent to the recorder's port,
+    /// was not timestamped by its queue, or cannot be represented in a MIDI file.
+    pub fn record(&mut self, e: &Event) -> bool {
+        if e.get_dest().port != self.port || e.get_queue() != self.queue { return false };
+        let tick = match e.get_tick() { Some(t) => t, None => return false };
+        let d = SmfData::from_event(e);
+        if d.is_empty() { return false };
+        let i = self.track_for(e.get_source());
+        let t = &mut self.tracks[i];
+        // Events might arrive out of order, and note offs are added ahead of time
+        for x in d { t.insert(tick, x) };
+        if e.get_type() == EventType::Note {
+            let n: EvNote = e.get_data().unwrap();
+            let off = SmfData::Midi(vec!(0x80 | (n.channel & 0xf), n.note & 0x7f, n.off_velocity & 0x7f));
+            t.insert(tick.saturating_add(n.duration), off);
+        }
+        true
+    }
+
+    /// Returns a format 1 file, with a tempo track followed by one track per source.
+    pub fn to_smf(&self) -> Smf {
+        let mut tempo = Track::new();
+        tempo.push(0, SmfData::tempo(self.tempo));
+        let mut tracks = vec!(tempo);
+        tracks.extend(self.tracks.iter().cloned());
+        Smf { format: Format::Multi, timing: Timing::Ppq(self.ppq), tracks: tracks }
+    }
+}
+
+#[test]
+fn smf_parse_write() {
+    let file: &[u8] = &[
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0, 96,
+        b'M', b'T', b'r', b'k', 0, 0, 0, 11,
+        0, 0xff, 0x51, 3, 0x07, 0xa1, 0x20,
+        0, 0xff, 0x2f, 0,
+        b'X', b'Y', b'Z', b'W', 0, 0, 0, 2, 1, 2, // Unknown chunk
+        b'M', b'T', b'r', b'k', 0, 0, 0, 21,
+        0, 0x90, 60, 100,
+        0x81, 0x40, 60, 0, // Running status, delta 192
+        0, 0xc1, 5,
+        0x20, 0xf0, 3, 1, 2, 0xf7,
+        0, 0xff, 0x2f, 0,
+    ];
+    let smf = Smf::parse(file).unwrap();
+    assert_eq!(smf.format, Format::Multi);
+    assert_eq!(smf.timing, Timing::Ppq(96));
+    assert_eq!(smf.tracks.len(), 2);
+    assert_eq!(smf.tracks[0].events[0].data.get_tempo(), Some(500000));
+    let t = &smf.tracks[1].events;
+    assert_eq!(t.len(), 5);
+    assert_eq!(t[1], SmfEvent { tick: 192, data: SmfData::Midi(vec!(0x90, 60, 0)) });
+    assert_eq!(t[2], SmfEvent { tick: 192, data: SmfData::Midi(vec!(0xc1, 5)) });
+    assert_eq!(t[3], SmfEvent { tick: 224, data: SmfData::Sysex(vec!(0xf0, 1, 2, 0xf7)) });
+    let m = smf.merged();
+    assert_eq!(m.len(), 7);
+    assert_eq!(m[0].data.get_tempo(), Some(500000));
+    assert_eq!(m[6].tick, 224);
+
+    let mut v = vec!();
+    smf.write(&mut v).unwrap();
+    assert_eq!(Smf::parse(&v).unwrap(), smf);
+
+    // End of track is added when missing
+    let mut smf2 = Smf::new(Format::Single, Timing::Smpte(25, 40));
+    let mut t = Track::new();
+    t.push(0x200000, SmfData::Escape(vec!(0xf8)));
+    smf2.tracks.push(t);
+    let mut v = vec!();
+    smf2.write(&mut v).unwrap();
+    let smf3 = Smf::parse(&v).unwrap();
+    assert_eq!(smf3.timing, Timing::Smpte(25, 40));
+    assert_eq!(smf3.tracks[0].events[0], smf2.tracks[0].events[0]);
+    assert_eq!(smf3.tracks[0].events[1], SmfEvent { tick: 0x200000, data: SmfData::Meta(META_END_OF_TRACK, vec!()) });
+
+    assert!(Smf::parse(&file[..30]).is_err());
+    assert!(Smf::parse(b"RIFF").is_err());
+    smf2.tracks[0].push(0x200000 + (1 << 28), SmfData::Escape(vec!(0xf8)));
+    assert_eq!(smf2.write(&mut vec!()).unwrap_err().kind(), ::std::io::ErrorKind::InvalidInput);
+    smf2.tracks[0].events.pop();
+    smf2.tracks.push(Track::new());
+    assert!(smf2.write(&mut vec!()).is_err());
+}
+
+#[test]
+fn smf_events() {
+    for d in &[vec!(0x93, 60, 100), vec!(0x80, 61, 64), vec!(0xa2, 62, 10), vec!(0xb0, 7, 127),
+        vec!(0xcf, 3), vec!(0xd1, 50), vec!(0xe0, 0, 0x40), vec!(0xe0, 0x7f, 0x7f), vec!(0xe0, 0, 0)] {
+        let d = SmfData::Midi(d.clone());
+        let e = d.to_event().unwrap();
+        assert_eq!(SmfData::from_event(&e), vec!(d));
+    }
+    let d = SmfData::Sysex(vec!(0xf0, 0x7e, 0x7f, 0x09, 0x01, 0xf7));
+    assert_eq!(SmfData::from_event(&d.to_event().unwrap()), vec!(d));
+    assert_eq!(SmfData::Meta(META_TRACK_NAME, vec!()).to_event().is_none(), true);
+
+    let e = Event::new(EventType::Regparam, &EvParam { channel: 1, param: 0x81, value: 0x3fff });
+    assert_eq!(SmfData::from_event(&e), vec!(SmfData::Midi(vec!(0xb1, 101, 1)), SmfData::Midi(vec!(0xb1, 100, 1)),
+        SmfData::Midi(vec!(0xb1, 6, 0x7f)), SmfData::Midi(vec!(0xb1, 38, 0x7f))));
+    let e = Event::new(EventType::Keysign, &EvKeySig { sharps: -2, minor: false });
+    assert_eq!(SmfData::from_event(&e), vec!(SmfData::Meta(META_KEY_SIGNATURE, vec!(0xfe, 0))));
+    assert!(SmfData::from_event(&Event::new(EventType::PortStart, &Addr { client: 1, port: 2 })).is_empty());
+
+    assert_eq!(SmfData::tempo(123456).get_tempo(), Some(123456));
+    assert_eq!(Timing::Ppq(480).queue_tempo(), (480, DEFAULT_TEMPO));
+    assert_eq!(Timing::Smpte(30, 80).queue_tempo(), (1200, 500000));
+    assert_eq!(Timing::Smpte(29, 80).queue_tempo(), (239760, 100000000));
+
+    let e = Event::new(EventType::Control14, &EvCtrl14 { channel: 2, controller: 7, value: 0x3fff });
+    assert_eq!(SmfData::from_event(&e), vec!(SmfData::Midi(vec!(0xb2, 7, 0x7f)), SmfData::Midi(vec!(0xb2, 39, 0x7f))));
+    let e = Event::new(EventType::Control14, &EvCtrl14 { channel: 2, controller: 240, value: 0x3fff });
+    assert!(SmfData::from_event(&e).is_empty());
+
+    let mut t = Track::new();
+    t.insert(10, SmfData::Midi(vec!(0x80, 60, 0)));
+    t.insert(0, SmfData::Midi(vec!(0x90, 60, 100)));
+    t.insert(10, SmfData::Midi(vec!(0x90, 62, 100)));
+    assert_eq!(t.events.iter().map(|e| e.tick).collect::<Vec<_>>(), vec!(0, 10, 10));
+    assert_eq!(t.events[2].data, SmfData::Midi(vec!(0x90, 62, 100)));
+}
+
+#[test]
+fn smf_play_record() {
+    use std::ffi::CString;
+    let s = Seq::open(None, None, false).unwrap();
+    s.set_client_name(&CString::new("rust_test_smf_play_record").unwrap()).unwrap();
+    let mut rec = Recorder::new(&s, &CString::new("recorder").unwrap(), 96, 250000).unwrap();
+    let sinfo = PortInfo::empty().unwrap();
+    sinfo.set_capability(seq::READ | seq::SUBS_READ);
+    s.create_port(&sinfo).unwrap();
+    let me = s.client_id().unwrap();
+    rec.connect_from(Addr { client: me, port: sinfo.get_port() }).unwrap();
+
+    let mut smf = Smf::new(Format::Single, Timing::Ppq(96));
+    let mut t = Track::new();
+    t.push(0, SmfData::tempo(100000));
+    t.push(0, SmfData::Midi(vec!(0x90, 60, 100)));
+    t.push(48, SmfData::Midi(vec!(0x80, 60, 0)));
+    smf.tracks.push(t);
+    let mut p = Player::new(&s, &smf, sinfo.get_port(), &[Addr { client: me, port: rec.get_port() }]).unwrap();
+    p.play().unwrap();
+    p.wait().unwrap();
+
+    let mut input = s.input();
+    for _ in 0..2 {
+        let e = input.event_input().unwrap();
+        assert!(rec.record(&e));
+    }
+    let r = rec.to_smf();
+    assert_eq!(r.tracks.len(), 2);
+    assert_eq!(r.tracks[1].events.iter().map(|e| e.data.clone()).collect::<Vec<_>>(),
+        vec!(SmfData::Midi(vec!(0x90, 60, 100)), SmfData::Midi(vec!(0x80, 60, 0))));
+    p.stop().unwrap();
+}
@@ -0,0 +1,601 @@
+//! Save and restore the control state of a card, like `alsactl store` and `alsactl restore`
+//!
+//! The text format is the one used by `asound.state`. The comment blocks (access, type, range etc)
+//! are informative only; values are restored by matching controls on name and index, falling back to numid.
+//!
+//! # Example
+//! Store the state of the first card, then restore it.
+//!
+//! ```no_run
+//! use alsa::state::CardState;
+//! let ctl = alsa::Ctl::new("hw:0", false).unwrap();
+//! let s = CardState::from_ctl(&ctl).unwrap().to_string();
+//! for c in CardState::parse(&s).unwrap() {
+//!     for m in c.restore(&ctl).unwrap() { println!("{:?}", m) }
+//! }
+//! ```
+
+use std::{fmt, slice, mem};
+use libc;
+use super::error::*;
+use super::ctl_int::{Ctl, ElemId, ElemIface, ElemType, ElemValue, ElemInfo};
+
+/// The values of a control
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The control was not readable when saved
+    None,
+    Boolean(Vec<bool>),
+    /// Values of integer and integer64 controls
+    Integer(Vec<i64>),
+    /// Item names (or indices, if names are not known)
+    Enumerated(Vec<String>),
+    Bytes(Vec<u8>),
+    /// The 24 channel status bytes
+    Iec958(Vec<u8>),
+}
+
+impl Value {
+    fn len(&self) -> usize {
+        match *self {
+            Value::None => 0,
+            Value::Boolean(ref v) => v.len(),
+            Value::Integer(ref v) => v.len(),
+            Value::Enumerated(ref v) => v.len(),
+            Value::Bytes(ref v) => v.len(),
+            Value::Iec958(_) => 1,
+        }
+    }
+}
+
+/// The saved state of one control
+#[derive(Debug, Clone, PartialEq)]
+pub struct Control {
+    pub numid: u32,
+    pub iface: ElemIface,
+    pub name: String,
+    pub index: u32,
+    pub device: u32,
+    pub subdevice: u32,
+    pub readable: bool,
+    pub writable: bool,
+    pub etype: ElemType,
+    pub count: u32,
+    /// Min, max and step, for integer controls
+    pub range: Option<(i64, i64, i64)>,
+    /// Item names, for enumerated controls
+    pub items: Vec<String>,
+    /// Raw TLV data, if the control has any.
+    ///
+    /// Like alsactl, this is written to and parsed from the text format in host byte order,
+    /// so the saved state is not portable between machines of different endianness.
+    pub tlv: Option<Vec<u32>>,
+    pub value: Value,
+}
+
+/// The saved state of all controls of a card
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardState {
+    /// The card's string id, e g, "PCH"
+    pub id: String,
+    pub controls: Vec<Control>,
+}
+
+/// Why a control could not be restored (or was restored in a different way than expected)
+#[derive(Debug, Clone)]
+pub enum MismatchKind {
+    /// The control was not found, neither by name and index, nor by numid
+    NotFound,
+    /// The control was not found by name and index, but a control with the same numid was used instead
+    MatchedByNumid,
+    /// The control on the card has this type
+    Type(ElemType),
+    /// The control on the card has this number of values
+    Count(u32),
+    /// An enumerated item name that does not exist on the card
+    UnknownItem(String),
+    /// An integer value outside the range of the control on the card
+    OutOfRange(i64),
+    /// Writing the value failed
+    Write(Error),
+}
+
+/// A control in the saved state that did not match the card
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub numid: u32,
+    pub name: String,
+    pub index: u32,
+    pub kind: MismatchKind,
+}
+
+static IFACE_NAMES: [(ElemIface, &'static str); 7] = [
+    (ElemIface::Card, "CARD"), (ElemIface::Hwdep, "HWDEP"), (ElemIface::Mixer, "MIXER"), (ElemIface::PCM, "PCM"),
+    (ElemIface::Rawmidi, "RAWMIDI"), (ElemIface::Timer, "TIMER"), (ElemIface::Sequencer, "SEQUENCER"),
+];
+
+static TYPE_NAMES: [(ElemType, &'static str); 7] = [
+    (ElemType::None, "NONE"), (ElemType::Boolean, "BOOLEAN"), (ElemType::Integer, "INTEGER"),
+    (ElemType::Enumerated, "ENUMERATED"), (ElemType::Bytes, "BYTES"), (ElemType::IEC958, "IEC958"),
+    (ElemType::Integer64, "INTEGER64"),
+];
+
+fn invalid() -> Error { Error::new("state::parse", libc::EINVAL) }
+
+impl Control {
+    fn from_ctl(ctl: &Ctl, id: &ElemId) -> Result<Control> {
+        let info = try!(ctl.elem_info(id));
+        let id = info.get_id();
+        let (t, count) = (info.get_type(), info.get_count());
+        let range = match t {
+            ElemType::Integer => Some((info.get_min().unwrap() as i64, info.get_max().unwrap() as i64, info.get_step().unwrap() as i64)),
+            ElemType::Integer64 => Some((info.get_min64().unwrap(), info.get_max64().unwrap(), info.get_step64().unwrap())),
+            _ => None,
+        };
+        let items = if t == ElemType::Enumerated { try!(ctl.elem_item_names(&info)) } else { vec!() };
+        let tlv = if info.is_tlv_readable() { ctl.elem_tlv_read(&id).ok().map(|t| t.to_raw()) } else { None };
+        let value = if info.is_readable() { try!(read_value(ctl, &info, &items)) } else { Value::None };
+        Ok(Control { numid: id.get_numid(), iface: id.get_interface(), name: try!(id.get_name()).into(),
+            index: id.get_index(), device: id.get_device(), subdevice: id.get_subdevice(),
+            readable: info.is_readable(), writable: info.is_writable(), etype: t, count: count,
+            range: range, items: items, tlv: tlv, value: value })
+    }
+
+    fn elem_id(&self) -> ElemId {
+        let mut id = ElemId::new(self.iface);
+        id.set_name(&::std::ffi::CString::new(&*self.name).unwrap());
+        id.set_index(self.index);
+        id.set_device(self.device);
+        id.set_subdevice(self.subdevice);
+        id
+    }
+
+    fn mismatch(&self, kind: MismatchKind) -> Mismatch {
+        Mismatch { numid: self.numid, name: self.name.clone(), index: self.index, kind: kind }
+    }
+
+    /// Finds the control on the card and writes the saved value to it.
+    fn restore(&self, ctl: &Ctl, res: &mut Vec<Mismatch>) -> Result<()> {
+        let info = match ctl.elem_info(&self.elem_id()) {
+            Ok(i) => i,
+            Err(_) => {
+                let mut id = ElemId::new(self.iface);
+                id.set_numid(self.numid);
+                match ctl.elem_info(&id) {
+                    Ok(i) => { res.push(self.mismatch(MismatchKind::MatchedByNumid)); i },
+                    Err(_) => { res.push(self.mismatch(MismatchKind::NotFound)); return Ok(()) },
+                }
+            }
+        };
+        if info.get_type() != self.etype { res.push(self.mismatch(MismatchKind::Type(info.get_type()))); return Ok(()) }
+        if self.value == Value::None || !info.is_writable() || info.is_inactive() { return Ok(()) }
+        let count = info.get_count();
+        if count as usize != self.value.len() && self.etype != ElemType::IEC958 {
+            res.push(self.mismatch(MismatchKind::Count(count)));
+            return Ok(())
+        }
+        let mut v = try!(ElemValue::new(self.etype));
+        v.set_id(&info.get_id());
+        match self.value {
+            Value::None => {},
+            Value::Boolean(ref b) => for (i, &x) in b.iter().enumerate() { v.set_boolean(i as u32, x); },
+            Value::Integer(ref b) => {
+                let (min, max) = if self.etype == ElemType::Integer64 { (info.get_min64().unwrap(), info.get_max64().unwrap()) }
+                    else { (info.get_min().unwrap() as i64, info.get_max().unwrap() as i64) };
+                if let Some(&x) = b.iter().find(|&&x| x < min || x > max) {
+                    res.push(self.mismatch(MismatchKind::OutOfRange(x)));
+                    return Ok(())
+                }
+                for (i, &x) in b.iter().enumerate() {
+                    if self.etype == ElemType::Integer64 { v.set_integer64(i as u32, x); }
+                    else { v.set_integer(i as u32, x as i32); }
+                }
+            },
+            Value::Enumerated(ref b) => {
+                let items = try!(ctl.elem_item_names(&info));
+                for (i, x) in b.iter().enumerate() {
+                    match items.iter().position(|n| n == x).or_else(|| x.parse().ok()) {
+                        Some(idx) => { v.set_enumerated(i as u32, idx as u32); },
+                        None => { res.push(self.mismatch(MismatchKind::UnknownItem(x.clone()))); return Ok(()) },
+                    }
+                }
+            },
+            Value::Bytes(ref b) => for (i, &x) in b.iter().enumerate() { v.set_byte(i as u32, x); },
+            Value::Iec958(ref b) => {
+                let mut a = v.get_iec958().unwrap();
+                for (d, &s) in a.status.iter_mut().zip(b.iter()) { *d = s };
+                v.set_iec958(&a);
+            },
+        }
+        if let Err(e) = ctl.elem_write(&v) { res.push(self.mismatch(MismatchKind::Write(e))) }
+        Ok(())
+    }
+}
+
+fn read_value(ctl: &Ctl, info: &ElemInfo, items: &[String]) -> Result<Value> {
+    let t = info.get_type();
+    let mut v = try!(ElemValue::new(t));
+    v.set_id(&info.get_id());
+    try!(ctl.elem_read(&mut v));
+    let r = 0..info.get_count();
+    Ok(match t {
+        ElemType::Boolean => Value::Boolean(r.map(|i| v.get_boolean(i).unwrap()).collect()),
+        ElemType::Integer => Value::Integer(r.map(|i| v.get_integer(i).unwrap() as i64).collect()),
+        ElemType::Integer64 => Value::Integer(r.map(|i| v.get_integer64(i).unwrap()).collect()),
+        ElemType::Enumerated => Value::Enumerated(r.map(|i| {
+            let x = v.get_enumerated(i).unwrap();
+            items.get(x as usize).cloned().unwrap_or_else(|| x.to_string())
+        }).collect()),
+        ElemType::Bytes => Value::Bytes(r.map(|i| v.get_byte(i).unwrap()).collect()),
+        ElemType::IEC958 => Value::Iec958(v.get_iec958().unwrap().status.to_vec()),
+        ElemType::None => Value::None,
+    })
+}
+
+impl CardState {
+    /// Saves the state of all controls of a card.
+    pub fn from_ctl(ctl: &Ctl) -> Result<CardState> {
+        let id = try!(try!(ctl.card_info()).get_id()).to_string();
+        let l = try!(ctl.elem_list());
+        let mut c = vec!();
+        for e in l.iter() { c.push(try!(Control::from_ctl(ctl, &e))) }
+        Ok(CardState { id: id, controls: c })
+    }
+
+    /// Writes the saved values to the card.
+    ///
+    /// Returns the controls that could not be matched or written. Controls that are not writable
+    /// (or not readable when saved) are skipped.
+    pub fn restore(&self, ctl: &Ctl) -> Result<Vec<Mismatch>> {
+        let mut res = vec!();
+        for c in &self.controls { try!(c.restore(ctl, &mut res)) }
+        Ok(res)
+    }
+
+    /// Parses the state of one or more cards, in `asound.state` format.
+    pub fn parse(s: &str) -> Result<Vec<CardState>> {
+        let mut t = Tokenizer(s.chars().peekable());
+        let root = try!(parse_compound(&mut t, false));
+        let states = match root.iter().find(|x| x.0 == "state") {
+            Some(&(_, Node::Compound(ref c))) => c,
+            _ => return Ok(vec!()),
+        };
+        states.iter().map(|&(ref id, ref n)| {
+            let mut controls = vec!();
+            for &(_, ref c) in try!(n.compound()).iter().filter(|x| x.0 == "control") {
+                for &(ref numid, ref c) in try!(c.compound()) {
+                    controls.push(try!(control_from_node(try!(numid.parse().map_err(|_| invalid())), c)))
+                }
+            }
+            Ok(CardState { id: id.clone(), controls: controls })
+        }).collect()
+    }
+}
+
+fn quote(s: &str) -> String {
+    let bare = s.len() > 0 && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !s.starts_with(|c: char| c.is_ascii_digit());
+    if bare { return s.into() }
+    let mut r = String::from("'");
+    for c in s.chars() {
+        if c == '\'' || c == '\\' { r.push('\\') }
+        r.push(c);
+    }
+    r.push('\'');
+    r
+}
+
+fn hex(b: &[u8]) -> String {
+    b.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn unhex(s: &str) -> Result<Vec<u8>> {
+    if s.len() % 2 != 0 { return Err(invalid()) }
+    (0..s.len()/2).map(|i| u8::from_str_radix(&s[2*i..2*i+2], 16).map_err(|_| invalid())).collect()
+}
+
+impl fmt::Display for CardState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(writeln!(f, "state.{} {{", quote(&self.id)));
+        for c in &self.controls {
+            try!(writeln!(f, "\tcontrol.{} {{", c.numid));
+            try!(writeln!(f, "\t\tiface {}", IFACE_NAMES.iter().find(|x| x.0 == c.iface).unwrap().1));
+            try!(writeln!(f, "\t\tname {}", quote(&c.name)));
+            if c.index != 0 { try!(writeln!(f, "\t\tindex {}", c.index)) }
+            if c.device != 0 { try!(writeln!(f, "\t\tdevice {}", c.device)) }
+            if c.subdevice != 0 { try!(writeln!(f, "\t\tsubdevice {}", c.subdevice)) }
+            let values: Vec<String> = match c.value {
+                Value::None => vec!(),
+                Value::Boolean(ref v) => v.iter().map(|x| x.to_string()).collect(),
+                Value::Integer(ref v) => v.iter().map(|x| x.to_string()).collect(),
+                Value::Enumerated(ref v) => v.iter().map(|x| quote(x)).collect(),
+                Value::Bytes(ref v) | Value::Iec958(ref v) => vec!(quote(&hex(v))),
+            };
+            if values.len() == 1 { try!(writeln!(f, "\t\tvalue {}", values[0])) }
+            else { for (i, v) in values.iter().enumerate() { try!(writeln!(f, "\t\tvalue.{} {}", i, v)) }}
+            try!(writeln!(f, "\t\tcomment {{"));
+            let access = match (c.readable, c.writable) { (true, true) => "'read write'", (true, false) => "read", (false, true) => "write", _ => "''" };
+            try!(writeln!(f, "\t\t\taccess {}", access));
+            try!(writeln!(f, "\t\t\ttype {}", TYPE_NAMES.iter().find(|x| x.0 == c.etype).unwrap().1));
+            try!(writeln!(f, "\t\t\tcount {}", c.count));
+            if let Some((min, max, step)) = c.range {
+                if step != 0 { try!(writeln!(f, "\t\t\trange '{} - {} (step {})'", min, max, step)) }
+                else { try!(writeln!(f, "\t\t\trange '{} - {}'", min, max)) }
+            }
+            for (i, n) in c.items.iter().enumerate() { try!(writeln!(f, "\t\t\titem.{} {}", i, quote(n))) }
+            if let Some(ref t) = c.tlv {
+                // Native byte order, like alsactl does
+                let b = unsafe { slice::from_raw_parts(t.as_ptr() as *const u8, t.len() * mem::size_of::<u32>()) };
+                try!(writeln!(f, "\t\t\ttlv {}", quote(&hex(b))));
+            }
+            try!(writeln!(f, "\t\t}}"));
+            try!(writeln!(f, "\t}}"));
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// A node in the (subset of the) alsa-lib configuration syntax used by asound.state
+#[derive(Debug)]
+enum Node {
+    Value(String),
+    Compound(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn compound(&self) -> Result<&[(String, Node)]> {
+        match *self { Node::Compound(ref c) => Ok(c), _ => Err(invalid()) }
+    }
+    fn value(&self) -> Result<&str> {
+        match *self { Node::Value(ref c) => Ok(c), _ => Err(invalid()) }
+    }
+    fn get(&self, key: &str) -> Option<&Node> {
+        match *self { Node::Compound(ref c) => c.iter().find(|x| x.0 == key).map(|x| &x.1), _ => None }
+    }
+    /// Either a single value, or a compound of values with keys "0", "1" and so on.
+    fn values(&self) -> Result<Vec<&str>> {
+        match *self {
+            Node::Value(ref s) => Ok(vec!(s)),
+            Node::Compound(ref c) => {
+                let mut v: Vec<(u32, &str)> = vec!();
+                for &(ref k, ref n) in c { v.push((try!(k.parse().map_err(|_| invalid())), try!(n.value()))) }
+                v.sort_by_key(|x| x.0);
+                Ok(v.into_iter().map(|x| x.1).collect())
+            }
+        }
+    }
+}
+
+/// Inserts a node at a dotted path, merging compounds with the same key.
+fn insert(c: &mut Vec<(String, Node)>, path: &[&str], n: Node) {
+    if path.len() == 1 {
+        if let Node::Compound(new) = n {
+            if let Some(&mut (_, Node::Compound(ref mut old))) = c.iter_mut().find(|x| x.0 == path[0]) {
+                for (k, v) in new { insert(old, &[&*k], v) }
+                return;
+            }
+            c.push((path[0].into(), Node::Compound(new)));
+        } else {
+            c.retain(|x| x.0 != path[0]);
+            c.push((path[0].into(), n));
+        }
+        return;
+    }
+    if c.iter().find(|x| x.0 == path[0]).map(|x| if let Node::Compound(_) = x.1 { false } else { true }).unwrap_or(true) {
+        c.retain(|x| x.0 != path[0]);
+        c.push((path[0].into(), Node::Compound(vec!())));
+    }
+    if let Some(&mut (_, Node::Compound(ref mut old))) = c.iter_mut().find(|x| x.0 == path[0]) {
+        insert(old, &path[1..], n)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token { Word(String), Quoted(String), Open, Close }
+
+struct Tokenizer<'a>(::std::iter::Peekable<::std::str::Chars<'a>>);
+
+impl<'a> Tokenizer<'a> {
+    fn next(&mut self) -> Result<Option<Token>> {
+        loop {
+            match self.0.peek().cloned() {
+                None => return Ok(None),
+                Some('#') => while let Some(c) = self.0.next() { if c == '\n' { break } },
+                Some(c) if c.is_whitespace() || c == ';' || c == ',' || c == '=' => { self.0.next(); },
+                Some(_) => break,
+            }
+        }
+        let c = self.0.next().unwrap();
+        Ok(Some(match c {
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '\'' | '"' => {
+                let mut s = String::new();
+                loop {
+                    match self.0.next() {
+                        None => return Err(invalid()),
+                        Some(x) if x == c => break,
+                        Some('\\') => match self.0.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(x) => s.push(x),
+                            None => return Err(invalid()),
+                        },
+                        Some(x) => s.push(x),
+                    }
+                }
+                Token::Quoted(s)
+            },
+            _ => {
+                let mut s = String::new();
+                s.push(c);
+                while let Some(&x) = self.0.peek() {
+                    if x.is_whitespace() || "{}'\";,=#".contains(x) { break }
+                    s.push(x);
+                    self.0.next();
+                }
+                Token::Word(s)
+            },
+        }))
+    }
+}
+
+fn parse_compound(t: &mut Tokenizer, nested: bool) -> Result<Vec<(String, Node)>> {
+    let mut c = vec!();
+    loop {
+        let key = match try!(t.next()) {
+            None if !nested => return Ok(c),
+            Some(Token::Close) if nested => return Ok(c),
+            Some(Token::Word(k)) | Some(Token::Quoted(k)) => k,
+            _ => return Err(invalid()),
+        };
+        let n = match try!(t.next()) {
+            Some(Token::Open) => Node::Compound(try!(parse_compound(t, true))),
+            Some(Token::Word(v)) | Some(Token::Quoted(v)) => Node::Value(v),
+            _ => return Err(invalid()),
+        };
+        let path: Vec<&str> = key.split('.').collect();
+        insert(&mut c, &path, n);
+    }
+}
+
+fn get<'a>(n: &'a Node, k: &str) -> Result<&'a str> {
+    n.get(k).map(|x| x.value()).unwrap_or(Ok(""))
+}
+
+fn num(n: &Node, k: &str) -> Result<u32> {
+    match n.get(k) { Some(x) => try!(x.value()).parse().map_err(|_| invalid()), None => Ok(0) }
+}
+
+fn control_from_node(numid: u32, n: &Node) -> Result<Control> {
+    let iface_name = try!(get(n, "iface"));
+    let iface = try!(IFACE_NAMES.iter().find(|x| x.1 == iface_name).map(|x| x.0).ok_or(invalid()));
+    let empty = Node::Compound(vec!());
+    let comment = n.get("comment").unwrap_or(&empty);
+    let values = match n.get("value") { Some(v) => try!(v.values()), None => vec!() };
+
+    let etype = match try!(get(comment, "type")) {
+        "" => if values.iter().all(|v| *v == "true" || *v == "false") && values.len() > 0 { ElemType::Boolean }
+            else if values.iter().all(|v| v.parse::<i64>().is_ok()) { ElemType::Integer }
+            else { ElemType::Enumerated },
+        s => try!(TYPE_NAMES.iter().find(|x| x.1 == s).map(|x| x.0).ok_or(invalid())),
+    };
+    let value = if values.len() == 0 { Value::None } else { match etype {
+        ElemType::Boolean => Value::Boolean(try!(values.iter().map(|v| match *v {
+            "true" | "on" | "1" => Ok(true), "false" | "off" | "0" => Ok(false), _ => Err(invalid()) }).collect::<Result<Vec<_>>>())),
+        ElemType::Integer | ElemType::Integer64 =>
+            Value::Integer(try!(values.iter().map(|v| v.parse().map_err(|_| invalid())).collect::<Result<Vec<_>>>())),
+        ElemType::Enumerated => Value::Enumerated(values.iter().map(|v| v.to_string()).collect()),
+        ElemType::Bytes => Value::Bytes(try!(unhex(values[0]))),
+        ElemType::IEC958 => Value::Iec958(try!(unhex(values[0]))),
+        ElemType::None => Value::None,
+    }};
+
+    let access = try!(get(comment, "access"));
+    let range = match comment.get("range") {
+        None => None,
+        Some(r) => {
+            let r: Vec<&str> = try!(r.value()).split(|c: char| c.is_whitespace() || c == '(' || c == ')').filter(|s| s.len() > 0).collect();
+            let p = |i: usize| r.get(i).and_then(|s| s.parse::<i64>().ok()).ok_or(invalid());
+            Some((try!(p(0)), try!(p(2)), if r.len() >= 5 { try!(p(4)) } else { 0 }))
+        },
+    };
+    let items = match comment.get("item") {
+        Some(i) => try!(i.values()).into_iter().map(|s| s.to_string()).collect(),
+        None => vec!(),
+    };
+    let tlv = match comment.get("tlv") {
+        Some(t) => {
+            let b = try!(unhex(try!(t.value())));
+            let mut v = vec![0u32; b.len() / 4];
+            unsafe { slice::from_raw_parts_mut(v.as_mut_ptr() as *mut u8, v.len() * 4) }.copy_from_slice(&b[..v.len() * 4]);
+            Some(v)
+        },
+        None => None,
+    };
+    let count = match comment.get("count") { Some(_) => try!(num(comment, "count")), None => value.len() as u32 };
+    // The name is passed to alsa-lib as a C string when restoring
+    let name = try!(get(n, "name"));
+    if name.contains('\0') { return Err(invalid()) }
+
+    Ok(Control { numid: numid, iface: iface, name: name.into(), index: try!(num(n, "index")),
+        device: try!(num(n, "device")), subdevice: try!(num(n, "subdevice")),
+        readable: access.contains("read"), writable: access.contains("write"), etype: etype, count: count,
+        range: range, items: items, tlv: tlv, value: value })
+}
+
+#[test]
+fn state_roundtrip() {
+    let s = r#"
+# Generated by alsactl
+state.PCH {
+	control.1 {
+		iface MIXER
+		name 'Headphone Playback Volume'
+		value.0 87
+		value.1 70
+		comment {
+			access 'read write'
+			type INTEGER
+			count 2
+			range '0 - 87'
+			dbmin -6525
+			dbmax 0
+		}
+	}
+	control.2 {
+		iface MIXER
+		name 'Capture Source'
+		index 1
+		value 'Rear Mic'
+		comment {
+			access 'read write'
+			type ENUMERATED
+			count 1
+			item.0 Mic
+			item.1 'Rear Mic'
+		}
+	}
+	control.3 {
+		iface MIXER
+		name 'IEC958 Playback Switch'
+		value true
+		comment { access 'read write' type BOOLEAN count 1 }
+	}
+	control.4 {
+		iface CARD
+		name 'Headphone Jack'
+		value false
+		comment { access read type BOOLEAN count 1 }
+	}
+}
+"#;
+    let c = CardState::parse(s).unwrap();
+    assert_eq!(c.len(), 1);
+    let c = &c[0];
+    assert_eq!(c.id, "PCH");
+    assert_eq!(c.controls.len(), 4);
+    assert_eq!(c.controls[0].value, Value::Integer(vec!(87, 70)));
+    assert_eq!(c.controls[0].range, Some((0, 87, 0)));
+    assert_eq!(c.controls[1].index, 1);
+    assert_eq!(c.controls[1].items, vec!("Mic".to_string(), "Rear Mic".to_string()));
+    assert_eq!(c.controls[1].value, Value::Enumerated(vec!("Rear Mic".into())));
+    assert_eq!(c.controls[2].value, Value::Boolean(vec!(true)));
+    assert_eq!(c.controls[3].iface, ElemIface::Card);
+    assert!(c.controls[3].readable && !c.controls[3].writable);
+
+    let s2 = c.to_string();
+    let c2 = CardState::parse(&s2).unwrap();
+    assert_eq!(&c2[0], c);
+    assert!(CardState::parse("state.X { control.1 { iface MIXER name 'AB' value 1 } }").is_ok());
+    assert!(CardState::parse("state.X { control.1 { iface MIXER name 'A\0B' value 1 } }").is_err());
+}
+
+#[test]
+fn print_state() {
+    for a in super::card::Iter::new().map(|x| x.unwrap()) {
+        let c = CardState::from_ctl(&a.ctl().unwrap()).unwrap();
+        let s = c.to_string();
+        println!("{}", s);
+        assert_eq!(CardState::parse(&s).unwrap()[0], c);
+    }
+}
@@ -0,0 +1,452 @@
+//! Timer API - system, hrtimer and PCM-slaved timers, plus enumeration
+//!
+//! # Example
+//! Get a tick every 10 ms from the high resolution timer.
+//!
+//! ```no_run
+//! use alsa::timer::{Timer, Params, Id, GLOBAL_HRTIMER};
+//!
+//! let t = Timer::open_id(&Id::global(GLOBAL_HRTIMER).unwrap(), false, false).unwrap();
+//! let res = t.info().unwrap().get_resolution(); // nanoseconds per tick
+//! let p = Params::new().unwrap();
+//! p.set_auto_start(true);
+//! p.set_ticks(10000000 / res);
+//! t.params(&p).unwrap();
+//! t.start().unwrap();
+//! let mut buf = [Default::default(); 4];
+//! let n = t.read(&mut buf).unwrap();
+//! println!("Got {} ticks", buf[..n].iter().map(|r| r.ticks).sum::<u32>());
+//! ```
+
+use libc::{c_int, c_uint, c_long, c_void, c_short, size_t, timespec, pollfd};
+use super::{Direction, poll};
+use super::error::*;
+use alsa;
+use std::{ptr, fmt, mem};
+use std::ffi::{CStr, CString};
+
+alsa_enum!(
+    /// [SND_TIMER_CLASS_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) constants
+    Class, ALL_CLASSES[5],
+
+    None = SND_TIMER_CLASS_NONE,
+    Slave = SND_TIMER_CLASS_SLAVE,
+    Global = SND_TIMER_CLASS_GLOBAL,
+    Card = SND_TIMER_CLASS_CARD,
+    Pcm = SND_TIMER_CLASS_PCM,
+);
+
+alsa_enum!(
+    /// [SND_TIMER_SCLASS_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) constants
+    SlaveClass, ALL_SLAVE_CLASSES[4],
+
+    None = SND_TIMER_SCLASS_NONE,
+    Application = SND_TIMER_SCLASS_APPLICATION,
+    Sequencer = SND_TIMER_SCLASS_SEQUENCER,
+    OssSequencer = SND_TIMER_SCLASS_OSS_SEQUENCER,
+);
+
+alsa_enum!(
+    /// [SND_TIMER_EVENT_xxx](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) constants
+    EventType, ALL_EVENT_TYPES[15],
+
+    Resolution = SND_TIMER_EVENT_RESOLUTION,
+    Tick = SND_TIMER_EVENT_TICK,
+    Start = SND_TIMER_EVENT_START,
+    Stop = SND_TIMER_EVENT_STOP,
+    Continue = SND_TIMER_EVENT_CONTINUE,
+    Pause = SND_TIMER_EVENT_PAUSE,
+    Early = SND_TIMER_EVENT_EARLY,
+    Suspend = SND_TIMER_EVENT_SUSPEND,
+    Resume = SND_TIMER_EVENT_RESUME,
+    MStart = SND_TIMER_EVENT_MSTART,
+    MStop = SND_TIMER_EVENT_MSTOP,
+    MContinue = SND_TIMER_EVENT_MCONTINUE,
+    MPause = SND_TIMER_EVENT_MPAUSE,
+    MSuspend = SND_TIMER_EVENT_MSUSPEND,
+    MResume = SND_TIMER_EVENT_MRESUME,
+);
+
+// FIXME: These constants do not exist in alsa-sys
+/// Device number of the system timer (jiffies)
+pub const GLOBAL_SYSTEM: i32 = 0;
+/// Device number of the RTC timer
+pub const GLOBAL_RTC: i32 = 1;
+/// Device number of the HPET timer
+pub const GLOBAL_HPET: i32 = 2;
+/// Device number of the high resolution timer
+pub const GLOBAL_HRTIMER: i32 = 3;
+
+const OPEN_NONBLOCK: c_int = 1;
+const OPEN_TREAD: c_int = 2;
+
+/// [snd_timer_id_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Id(*mut alsa::snd_timer_id_t);
+
+unsafe impl Send for Id {}
+
+impl Drop for Id {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_id_free(self.0) }; }
+}
+
+impl Clone for Id {
+    fn clone(&self) -> Self { let r = Id::new().unwrap(); unsafe { alsa::snd_timer_id_copy(r.0, self.0) }; r }
+}
+
+impl Id {
+    pub fn new() -> Result<Id> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_id_malloc(&mut p)).map(|_| Id(p))
+    }
+
+    /// Id of a global timer, e g, `GLOBAL_HRTIMER`.
+    pub fn global(device: i32) -> Result<Id> {
+        let r = try!(Id::new());
+        r.set_class(Class::Global);
+        r.set_sclass(SlaveClass::None);
+        r.set_card(-1);
+        r.set_device(device);
+        r.set_subdevice(0);
+        Ok(r)
+    }
+
+    /// Id of the timer slaved to a PCM substream, which ticks once per period.
+    pub fn pcm(card: i32, device: i32, subdevice: i32, dir: Direction) -> Result<Id> {
+        let r = try!(Id::new());
+        r.set_class(Class::Pcm);
+        r.set_sclass(SlaveClass::None);
+        r.set_card(card);
+        r.set_device(device);
+        r.set_subdevice((subdevice << 1) | if dir == Direction::Capture { 1 } else { 0 });
+        Ok(r)
+    }
+
+    pub fn get_class(&self) -> Result<Class> {
+        Class::from_c_int(unsafe { alsa::snd_timer_id_get_class(self.0) }, "snd_timer_id_get_class")
+    }
+    pub fn get_sclass(&self) -> Result<SlaveClass> {
+        SlaveClass::from_c_int(unsafe { alsa::snd_timer_id_get_sclass(self.0) }, "snd_timer_id_get_sclass")
+    }
+    pub fn get_card(&self) -> i32 { unsafe { alsa::snd_timer_id_get_card(self.0) as i32 }}
+    pub fn get_device(&self) -> i32 { unsafe { alsa::snd_timer_id_get_device(self.0) as i32 }}
+    pub fn get_subdevice(&self) -> i32 { unsafe { alsa::snd_timer_id_get_subdevice(self.0) as i32 }}
+
+    pub fn set_class(&self, v: Class) { unsafe { alsa::snd_timer_id_set_class(self.0, v as c_int) }}
+    pub fn set_sclass(&self, v: SlaveClass) { unsafe { alsa::snd_timer_id_set_sclass(self.0, v as c_int) }}
+    pub fn set_card(&self, v: i32) { unsafe { alsa::snd_timer_id_set_card(self.0, v as c_int) }}
+    pub fn set_device(&self, v: i32) { unsafe { alsa::snd_timer_id_set_device(self.0, v as c_int) }}
+    pub fn set_subdevice(&self, v: i32) { unsafe { alsa::snd_timer_id_set_subdevice(self.0, v as c_int) }}
+
+    /// Returns the name to give to `Timer::open` to open this timer.
+    pub fn to_name(&self) -> String {
+        format!("hw:CLASS={},SCLASS={},CARD={},DEV={},SUBDEV={}", unsafe { alsa::snd_timer_id_get_class(self.0) },
+            unsafe { alsa::snd_timer_id_get_sclass(self.0) }, self.get_card(), self.get_device(), self.get_subdevice())
+    }
+}
+
+impl fmt::Debug for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "Id({})", self.to_name()) }
+}
+
+pub(crate) fn id_ptr(i: &Id) -> *mut alsa::snd_timer_id_t { i.0 }
+
+/// [snd_timer_query_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Query(*mut alsa::snd_timer_query_t);
+
+impl Drop for Query {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_query_close(self.0) }; }
+}
+
+impl Query {
+    /// Opens the default ("hw") timer query interface.
+    pub fn new() -> Result<Query> { Query::open(&CString::new("hw").unwrap()) }
+
+    pub fn open(name: &CStr) -> Result<Query> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_query_open(&mut p, name.as_ptr(), 0)).map(|_| Query(p))
+    }
+
+    /// Iterates over all timers in the system.
+    pub fn iter<'a>(&'a self) -> Iter<'a> { Iter(self, None, false) }
+
+    pub fn info(&self, id: &Id) -> Result<GInfo> {
+        let i = try!(GInfo::new());
+        try!(acheck!(snd_timer_ginfo_set_tid(i.0, id.0)));
+        acheck!(snd_timer_query_info(self.0, i.0)).map(|_| i)
+    }
+}
+
+/// Iterator over timers, see `Query::iter`
+///
+/// Stops after the last timer, or after the first error.
+pub struct Iter<'a>(&'a Query, Option<Id>, bool);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Id>;
+    fn next(&mut self) -> Option<Result<Id>> {
+        if self.2 { return None }
+        if self.1.is_none() {
+            match Id::new() {
+                Err(e) => { self.2 = true; return Some(Err(e)) },
+                Ok(id) => { id.set_class(Class::None); self.1 = Some(id) },
+            }
+        }
+        let id = self.1.as_ref().unwrap();
+        if let Err(e) = acheck!(snd_timer_query_next_device((self.0).0, id.0)) { self.2 = true; return Some(Err(e)) };
+        if unsafe { alsa::snd_timer_id_get_class(id.0) } < 0 { self.2 = true; None } else { Some(Ok(id.clone())) }
+    }
+}
+
+/// [snd_timer_ginfo_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct GInfo(*mut alsa::snd_timer_ginfo_t);
+
+impl Drop for GInfo {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_ginfo_free(self.0) }; }
+}
+
+impl GInfo {
+    fn new() -> Result<GInfo> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_ginfo_malloc(&mut p)).map(|_| GInfo(p))
+    }
+
+    pub fn get_tid(&self) -> Result<Id> {
+        let r = try!(Id::new());
+        unsafe { alsa::snd_timer_id_copy(r.0, alsa::snd_timer_ginfo_get_tid(self.0)) };
+        Ok(r)
+    }
+    pub fn get_flags(&self) -> u32 { unsafe { alsa::snd_timer_ginfo_get_flags(self.0) as u32 }}
+    pub fn get_card(&self) -> i32 { unsafe { alsa::snd_timer_ginfo_get_card(self.0) as i32 }}
+    pub fn get_id(&self) -> Result<&str> { from_const("snd_timer_ginfo_get_id", unsafe { alsa::snd_timer_ginfo_get_id(self.0) })}
+    pub fn get_name(&self) -> Result<&str> { from_const("snd_timer_ginfo_get_name", unsafe { alsa::snd_timer_ginfo_get_name(self.0) })}
+    /// Resolution in nanoseconds per tick
+    pub fn get_resolution(&self) -> u64 { unsafe { alsa::snd_timer_ginfo_get_resolution(self.0) as u64 }}
+    pub fn get_resolution_min(&self) -> u64 { unsafe { alsa::snd_timer_ginfo_get_resolution_min(self.0) as u64 }}
+    pub fn get_resolution_max(&self) -> u64 { unsafe { alsa::snd_timer_ginfo_get_resolution_max(self.0) as u64 }}
+    pub fn get_clients(&self) -> u32 { unsafe { alsa::snd_timer_ginfo_get_clients(self.0) as u32 }}
+}
+
+/// [snd_timer_info_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Info(*mut alsa::snd_timer_info_t);
+
+impl Drop for Info {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_info_free(self.0) }; }
+}
+
+impl Info {
+    fn new() -> Result<Info> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_info_malloc(&mut p)).map(|_| Info(p))
+    }
+
+    pub fn is_slave(&self) -> bool { unsafe { alsa::snd_timer_info_is_slave(self.0) != 0 }}
+    pub fn get_card(&self) -> i32 { unsafe { alsa::snd_timer_info_get_card(self.0) as i32 }}
+    pub fn get_id(&self) -> Result<&str> { from_const("snd_timer_info_get_id", unsafe { alsa::snd_timer_info_get_id(self.0) })}
+    pub fn get_name(&self) -> Result<&str> { from_const("snd_timer_info_get_name", unsafe { alsa::snd_timer_info_get_name(self.0) })}
+    /// Resolution in nanoseconds per tick
+    pub fn get_resolution(&self) -> i64 { unsafe { alsa::snd_timer_info_get_resolution(self.0) as i64 }}
+    pub fn get_ticks(&self) -> i64 { unsafe { alsa::snd_timer_info_get_ticks(self.0) as i64 }}
+}
+
+/// [snd_timer_params_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Params(*mut alsa::snd_timer_params_t);
+
+impl Drop for Params {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_params_free(self.0) }; }
+}
+
+impl Params {
+    pub fn new() -> Result<Params> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_params_malloc(&mut p)).map(|_| Params(p))
+    }
+
+    pub fn set_auto_start(&self, v: bool) { unsafe { alsa::snd_timer_params_set_auto_start(self.0, if v { 1 } else { 0 }) }; }
+    pub fn get_auto_start(&self) -> bool { unsafe { alsa::snd_timer_params_get_auto_start(self.0) != 0 }}
+    pub fn set_exclusive(&self, v: bool) { unsafe { alsa::snd_timer_params_set_exclusive(self.0, if v { 1 } else { 0 }) }; }
+    pub fn get_exclusive(&self) -> bool { unsafe { alsa::snd_timer_params_get_exclusive(self.0) != 0 }}
+    /// Deliver an `EventType::Early` event before the first tick (tread mode only)
+    pub fn set_early_event(&self, v: bool) { unsafe { alsa::snd_timer_params_set_early_event(self.0, if v { 1 } else { 0 }) }; }
+    pub fn get_early_event(&self) -> bool { unsafe { alsa::snd_timer_params_get_early_event(self.0) != 0 }}
+    /// Number of timer ticks (of the resolution given by `Info::get_resolution`) between each event
+    pub fn set_ticks(&self, v: i64) { unsafe { alsa::snd_timer_params_set_ticks(self.0, v as c_long) }}
+    pub fn get_ticks(&self) -> i64 { unsafe { alsa::snd_timer_params_get_ticks(self.0) as i64 }}
+    pub fn set_queue_size(&self, v: i64) { unsafe { alsa::snd_timer_params_set_queue_size(self.0, v as c_long) }}
+    pub fn get_queue_size(&self) -> i64 { unsafe { alsa::snd_timer_params_get_queue_size(self.0) as i64 }}
+
+    /// Selects which events are delivered in tread mode. Only events in the filter are delivered,
+    /// so an empty slice means no events at all; include `EventType::Tick` to get the ticks.
+    pub fn set_filter(&self, v: &[EventType]) {
+        let f = v.iter().fold(0, |acc, &e| acc | (1 << (e as c_uint)));
+        unsafe { alsa::snd_timer_params_set_filter(self.0, f) }
+    }
+    pub fn get_filter(&self) -> Vec<EventType> {
+        let f = unsafe { alsa::snd_timer_params_get_filter(self.0) };
+        EventType::all().iter().filter(|&&e| f & (1 << (e as c_uint)) != 0).map(|&e| e).collect()
+    }
+}
+
+/// [snd_timer_status_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Status(*mut alsa::snd_timer_status_t);
+
+impl Drop for Status {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_status_free(self.0) }; }
+}
+
+impl Status {
+    fn new() -> Result<Status> {
+        let mut p = ptr::null_mut();
+        acheck!(snd_timer_status_malloc(&mut p)).map(|_| Status(p))
+    }
+
+    pub fn get_timestamp(&self) -> timespec { unsafe { alsa::snd_timer_status_get_timestamp(self.0) }}
+    pub fn get_resolution(&self) -> i64 { unsafe { alsa::snd_timer_status_get_resolution(self.0) as i64 }}
+    /// Number of ticks lost because the application did not read them in time
+    pub fn get_lost(&self) -> i64 { unsafe { alsa::snd_timer_status_get_lost(self.0) as i64 }}
+    pub fn get_overrun(&self) -> i64 { unsafe { alsa::snd_timer_status_get_overrun(self.0) as i64 }}
+    pub fn get_queue(&self) -> i64 { unsafe { alsa::snd_timer_status_get_queue(self.0) as i64 }}
+}
+
+/// A tick event, as returned by `Timer::read`. Same layout as `snd_timer_read_t`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Read {
+    /// Resolution in nanoseconds per tick
+    pub resolution: u32,
+    /// Number of ticks elapsed since the last read
+    pub ticks: u32,
+}
+
+/// A timestamped event, as returned by `Timer::read_tread`. Same layout as `snd_timer_tread_t`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct TRead {
+    event: c_uint,
+    tstamp: timespec,
+    val: c_uint,
+}
+
+impl Default for TRead {
+    fn default() -> TRead { TRead { event: 0, tstamp: timespec { tv_sec: 0, tv_nsec: 0 }, val: 0 } }
+}
+
+impl TRead {
+    pub fn get_event(&self) -> Result<EventType> { EventType::from_c_int(self.event as c_int, "snd_timer_tread_t") }
+    pub fn get_tstamp(&self) -> timespec { self.tstamp }
+    /// The number of ticks for `EventType::Tick`, the resolution for `EventType::Resolution`
+    pub fn get_val(&self) -> u32 { self.val as u32 }
+}
+
+impl fmt::Debug for TRead {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TRead {{ event: {:?}, tstamp: {}.{:09}, val: {} }}", self.get_event(), self.tstamp.tv_sec, self.tstamp.tv_nsec, self.val)
+    }
+}
+
+/// [snd_timer_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group___timer.html) wrapper
+pub struct Timer(*mut alsa::snd_timer_t, bool);
+
+unsafe impl Send for Timer {}
+
+impl Drop for Timer {
+    fn drop(&mut self) { unsafe { alsa::snd_timer_close(self.0) }; }
+}
+
+impl Timer {
+    /// Wrapper around open that takes a &str instead of a &CStr
+    pub fn new(name: &str, nonblock: bool, tread: bool) -> Result<Self> {
+        Self::open(&CString::new(name).unwrap(), nonblock, tread)
+    }
+
+    /// Opens a timer by name, e g, "hw:CLASS=1,SCLASS=0,DEV=3".
+    ///
+    /// If `tread` is true, events are read with `read_tread`, otherwise with `read`.
+    pub fn open(name: &CStr, nonblock: bool, tread: bool) -> Result<Timer> {
+        let mut h = ptr::null_mut();
+        let flags = (if nonblock { OPEN_NONBLOCK } else { 0 }) | (if tread { OPEN_TREAD } else { 0 });
+        acheck!(snd_timer_open(&mut h, name.as_ptr(), flags)).map(|_| Timer(h, tread))
+    }
+
+    pub fn open_id(id: &Id, nonblock: bool, tread: bool) -> Result<Timer> { Self::new(&id.to_name(), nonblock, tread) }
+
+    pub fn info(&self) -> Result<Info> {
+        Info::new().and_then(|i| acheck!(snd_timer_info(self.0, i.0)).map(|_| i))
+    }
+
+    pub fn params(&self, p: &Params) -> Result<()> { acheck!(snd_timer_params(self.0, p.0)).map(|_| ()) }
+
+    pub fn status(&self) -> Result<Status> {
+        Status::new().and_then(|s| acheck!(snd_timer_status(self.0, s.0)).map(|_| s))
+    }
+
+    pub fn start(&self) -> Result<()> { acheck!(snd_timer_start(self.0)).map(|_| ()) }
+    pub fn stop(&self) -> Result<()> { acheck!(snd_timer_stop(self.0)).map(|_| ()) }
+    /// Wrapper around `snd_timer_continue`
+    pub fn cont(&self) -> Result<()> { acheck!(snd_timer_continue(self.0)).map(|_| ()) }
+
+    /// Reads tick events, returns the number of events read. Requires the timer to be opened with tread = false.
+    pub fn read(&self, buf: &mut [Read]) -> Result<usize> {
+        if self.1 { return Err(Error::new("snd_timer_read", ::libc::EINVAL)) }
+        self.read_raw(buf.as_mut_ptr() as *mut c_void, buf.len(), mem::size_of::<Read>())
+    }
+
+    /// Reads timestamped events, returns the number of events read. Requires the timer to be opened with tread = true.
+    pub fn read_tread(&self, buf: &mut [TRead]) -> Result<usize> {
+        if !self.1 { return Err(Error::new("snd_timer_read", ::libc::EINVAL)) }
+        self.read_raw(buf.as_mut_ptr() as *mut c_void, buf.len(), mem::size_of::<TRead>())
+    }
+
+    fn read_raw(&self, buf: *mut c_void, count: usize, size: usize) -> Result<usize> {
+        let r = unsafe { alsa::snd_timer_read(self.0, buf, (count * size) as size_t) };
+        if r < 0 { Err(Error::new("snd_timer_read", -r as c_int)) }
+        else { Ok(r as usize / size) }
+    }
+}
+
+impl poll::PollDescriptors for Timer {
+    fn count(&self) -> usize {
+        unsafe { alsa::snd_timer_poll_descriptors_count(self.0) as usize }
+    }
+    fn fill(&self, p: &mut [pollfd]) -> Result<usize> {
+        let z = unsafe { alsa::snd_timer_poll_descriptors(self.0, p.as_mut_ptr(), p.len() as c_uint) };
+        from_code("snd_timer_poll_descriptors", z).map(|_| z as usize)
+    }
+    fn revents(&self, p: &[pollfd]) -> Result<poll::PollFlags> {
+        let mut r = 0;
+        let z = unsafe { alsa::snd_timer_poll_descriptors_revents(self.0, p.as_ptr() as *mut pollfd, p.len() as c_uint, &mut r) };
+        from_code("snd_timer_poll_descriptors_revents", z).map(|_| poll::PollFlags::from_bits_truncate(r as c_short))
+    }
+}
+
+#[test]
+fn print_timers() {
+    let q = Query::new().unwrap();
+    for id in q.iter().map(|id| id.unwrap()) {
+        let i = q.info(&id).unwrap();
+        println!("Timer {:?}: {} ({}) - resolution {} ns, {} clients", id, i.get_name().unwrap(), i.get_id().unwrap(),
+            i.get_resolution(), i.get_clients());
+    }
+    let mut it = q.iter();
+    while it.next().is_some() {}
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn timer_ticks() {
+    let t = Timer::open_id(&Id::global(GLOBAL_SYSTEM).unwrap(), false, true).unwrap();
+    let res = t.info().unwrap().get_resolution();
+    let p = Params::new().unwrap();
+    p.set_auto_start(true);
+    p.set_ticks(1 + 5000000 / res);
+    p.set_filter(&[EventType::Tick]);
+    assert_eq!(p.get_filter(), vec!(EventType::Tick));
+    t.params(&p).unwrap();
+    t.start().unwrap();
+    let mut buf = [TRead::default(); 4];
+    let n = t.read_tread(&mut buf).unwrap();
+    assert!(n > 0);
+    println!("{:?}", &buf[..n]);
+    assert_eq!(buf[0].get_event().unwrap(), EventType::Tick);
+    assert!(t.read(&mut [Read::default()]).is_err());
+    t.stop().unwrap();
+}
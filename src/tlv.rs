@@ -0,0 +1,385 @@
+//! Decoding and encoding of TLV (Type-Length-Value) data, as read from and written to control elements.
+
+use libc::c_int;
+use super::error::*;
+use super::mixer::MilliBel;
+use super::chmap::{ChmapType, ChmapPosition};
+use super::Round;
+
+// Some constants that are not in alsa-sys
+const SND_CTL_TLVT_CONTAINER: u32 = 0;
+const SND_CTL_TLVT_DB_SCALE: u32 = 1;
+const SND_CTL_TLVT_DB_LINEAR: u32 = 2;
+const SND_CTL_TLVT_DB_RANGE: u32 = 3;
+const SND_CTL_TLVT_DB_MINMAX: u32 = 4;
+const SND_CTL_TLVT_DB_MINMAX_MUTE: u32 = 5;
+const SND_CTL_TLVT_CHMAP_FIXED: u32 = 0x101;
+const SND_CTL_TLVT_CHMAP_VAR: u32 = 0x102;
+const SND_CTL_TLVT_CHMAP_PAIRED: u32 = 0x103;
+const SND_CTL_TLV_DB_SCALE_MUTE: u32 = 0x10000;
+
+/// The gain used by alsa-lib to signal "muted", i e, minus infinity
+pub const DB_GAIN_MUTE: MilliBel = MilliBel(-9999999);
+
+fn invalid(func: &'static str) -> Error { Error::new(func, ::libc::EINVAL) }
+
+/// A dB scale, as described by one of the SNDRV_CTL_TLVT_DB_xxx TLV types.
+///
+/// The `to_db`, `from_db` and `get_db_range` functions mirror the `snd_tlv_convert_to_dB`,
+/// `snd_tlv_convert_from_dB` and `snd_tlv_get_dB_range` functions in alsa-lib.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbScale {
+    /// Linear volume between min and max; min can be `DB_GAIN_MUTE`
+    Linear { min: MilliBel, max: MilliBel },
+    /// dB values linearly spread between min and max
+    MinMax { min: MilliBel, max: MilliBel },
+    /// Like `MinMax`, but the lowest value is muted
+    MinMaxMute { min: MilliBel, max: MilliBel },
+    /// Each step is `step` dB, starting at `min`. If mute is true, the lowest value is muted
+    Scale { min: MilliBel, step: MilliBel, mute: bool },
+    /// Different scales for different parts of the element's value range
+    Range(Vec<DbRangeItem>),
+}
+
+/// One part of a `DbScale::Range`; applies to raw values from `min` to `max`, inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbRangeItem {
+    pub min: i64,
+    pub max: i64,
+    pub scale: DbScale,
+}
+
+fn pow10(mb: i64) -> f64 { 10f64.powf(mb as f64 / 2000.0) }
+
+impl DbScale {
+    /// Returns the lowest and highest possible gain, given the element's value range.
+    pub fn get_db_range(&self, rangemin: i64, rangemax: i64) -> Result<(MilliBel, MilliBel)> {
+        Ok(match *self {
+            DbScale::Range(ref items) => {
+                let mut r: Option<(MilliBel, MilliBel)> = None;
+                for i in items {
+                    // Like alsa-lib, don't go past the element's range
+                    let (lo, hi) = try!(i.scale.get_db_range(i.min, ::std::cmp::min(i.max, rangemax)));
+                    r = Some(match r {
+                        None => (lo, hi),
+                        Some((a, b)) => (if lo.0 < a.0 { lo } else { a }, if hi.0 > b.0 { hi } else { b }),
+                    });
+                }
+                try!(r.ok_or_else(|| invalid("snd_tlv_get_dB_range")))
+            },
+            DbScale::Scale { min, step, mute } =>
+                (if mute { DB_GAIN_MUTE } else { min }, MilliBel(min.0 + step.0 * (rangemax - rangemin))),
+            DbScale::MinMaxMute { max, .. } => (DB_GAIN_MUTE, max),
+            DbScale::MinMax { min, max } | DbScale::Linear { min, max } => (min, max),
+        })
+    }
+
+    /// Converts a raw element value to a gain.
+    pub fn to_db(&self, value: i64, rangemin: i64, rangemax: i64) -> Result<MilliBel> {
+        Ok(match *self {
+            DbScale::Range(ref items) => {
+                let i = try!(items.iter().find(|i| value >= i.min && value <= i.max)
+                    .ok_or_else(|| invalid("snd_tlv_convert_to_dB")));
+                return i.scale.to_db(value, i.min, i.max);
+            },
+            DbScale::Scale { min, step, mute } => {
+                if mute && value <= rangemin { DB_GAIN_MUTE }
+                else { MilliBel((value - rangemin) * step.0 + min.0) }
+            },
+            DbScale::MinMax { min, max } | DbScale::MinMaxMute { min, max } => {
+                let m = if let DbScale::MinMaxMute { .. } = *self { true } else { false };
+                if value <= rangemin || rangemax <= rangemin { if m { DB_GAIN_MUTE } else { min } }
+                else if value >= rangemax { max }
+                else { MilliBel(min.0 + (max.0 - min.0) * (value - rangemin) / (rangemax - rangemin)) }
+            },
+            DbScale::Linear { min, max } => {
+                if value <= rangemin { min }
+                else if value >= rangemax { max }
+                else {
+                    let vmin = if min.0 <= DB_GAIN_MUTE.0 { 0.0 } else { pow10(min.0) };
+                    let vmax = pow10(max.0);
+                    let v = (value - rangemin) as f64 * (vmax - vmin) / (rangemax - rangemin) as f64 + vmin;
+                    if v <= 0.0 { DB_GAIN_MUTE } else { MilliBel((2000.0 * v.log10()) as i64) }
+                }
+            },
+        })
+    }
+
+    /// Converts a gain to a raw element value, rounding in the direction given.
+    pub fn from_db(&self, db: MilliBel, rangemin: i64, rangemax: i64, dir: Round) -> Result<i64> {
+        let ceil = dir == Round::Ceil;
+        Ok(match *self {
+            DbScale::Range(ref items) => {
+                let mut prev_max = 0;
+                for (n, i) in items.iter().enumerate() {
+                    let max = ::std::cmp::min(i.max, rangemax);
+                    let (lo, hi) = try!(i.scale.get_db_range(i.min, max));
+                    if db.0 >= lo.0 && db.0 <= hi.0 { return i.scale.from_db(db, i.min, max, dir) }
+                    if db.0 < lo.0 { return Ok(if ceil || n == 0 { i.min } else { prev_max }) }
+                    prev_max = max;
+                }
+                prev_max
+            },
+            DbScale::Scale { min, step, mute } => {
+                let max = min.0 + step.0 * (rangemax - rangemin);
+                if db.0 <= min.0 {
+                    if db.0 > DB_GAIN_MUTE.0 && ceil && mute { rangemin + 1 } else { rangemin }
+                }
+                else if db.0 >= max { rangemax }
+                else { scale_from_db(db.0, min.0, max, rangemin, rangemax, ceil) }
+            },
+            DbScale::MinMax { min, max } | DbScale::MinMaxMute { min, max } => {
+                let m = if let DbScale::MinMaxMute { .. } = *self { true } else { false };
+                if db.0 <= min.0 {
+                    if db.0 > DB_GAIN_MUTE.0 && ceil && m { rangemin + 1 } else { rangemin }
+                }
+                else if db.0 >= max.0 { rangemax }
+                else { scale_from_db(db.0, min.0, max.0, rangemin, rangemax, ceil) }
+            },
+            DbScale::Linear { min, max } => {
+                if db.0 <= min.0 { rangemin }
+                else if db.0 >= max.0 { rangemax }
+                else {
+                    let vmin = if min.0 <= DB_GAIN_MUTE.0 { 0.0 } else { pow10(min.0) };
+                    let vmax = pow10(max.0);
+                    let v = (pow10(db.0) - vmin) * (rangemax - rangemin) as f64 / (vmax - vmin);
+                    (if ceil { v.ceil() } else { v }) as i64 + rangemin
+                }
+            },
+        })
+    }
+
+    fn to_raw(&self, v: &mut Vec<u32>) {
+        match *self {
+            DbScale::Range(ref items) => {
+                let start = push_header(v, SND_CTL_TLVT_DB_RANGE);
+                for i in items {
+                    v.push(i.min as u32);
+                    v.push(i.max as u32);
+                    i.scale.to_raw(v);
+                }
+                set_length(v, start);
+            },
+            DbScale::Scale { min, step, mute } => {
+                let s = (step.0 as u32 & 0xffff) | if mute { SND_CTL_TLV_DB_SCALE_MUTE } else { 0 };
+                v.extend_from_slice(&[SND_CTL_TLVT_DB_SCALE, 2 * 4, min.0 as c_int as u32, s]);
+            },
+            DbScale::Linear { min, max } =>
+                v.extend_from_slice(&[SND_CTL_TLVT_DB_LINEAR, 2 * 4, min.0 as c_int as u32, max.0 as c_int as u32]),
+            DbScale::MinMax { min, max } =>
+                v.extend_from_slice(&[SND_CTL_TLVT_DB_MINMAX, 2 * 4, min.0 as c_int as u32, max.0 as c_int as u32]),
+            DbScale::MinMaxMute { min, max } =>
+                v.extend_from_slice(&[SND_CTL_TLVT_DB_MINMAX_MUTE, 2 * 4, min.0 as c_int as u32, max.0 as c_int as u32]),
+        }
+    }
+}
+
+fn scale_from_db(db: i64, min: i64, max: i64, rangemin: i64, rangemax: i64, ceil: bool) -> i64 {
+    let mut v = (db - min) * (rangemax - rangemin);
+    if ceil { v += (max - min) - 1 }
+    v / (max - min) + rangemin
+}
+
+fn push_header(v: &mut Vec<u32>, t: u32) -> usize {
+    v.push(t);
+    v.push(0);
+    v.len()
+}
+
+fn set_length(v: &mut Vec<u32>, start: usize) {
+    v[start - 1] = ((v.len() - start) * 4) as u32;
+}
+
+/// Decoded TLV data, see `Ctl::elem_tlv_read`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tlv {
+    /// Several TLVs, e g, a dB scale together with a channel map
+    Container(Vec<Tlv>),
+    Db(DbScale),
+    /// Channel positions. Positions with driver specific or phase inverse flags set are decoded as `Tlv::Unknown`.
+    Chmap(ChmapType, Vec<ChmapPosition>),
+    /// A TLV type this decoder does not know about, with its data.
+    Unknown(u32, Vec<u32>),
+}
+
+impl Tlv {
+    /// Decodes raw TLV data, as stored in the kernel.
+    pub fn from_raw(v: &[u32]) -> Result<Tlv> {
+        let (t, _) = try!(parse(v));
+        Ok(t)
+    }
+
+    /// Encodes to raw TLV data, suitable for `Ctl::elem_tlv_write` and friends.
+    pub fn to_raw(&self) -> Vec<u32> {
+        let mut v = vec!();
+        self.push_raw(&mut v);
+        v
+    }
+
+    /// Returns the dB scale, if this TLV has one (directly or inside a container).
+    pub fn get_db_scale(&self) -> Option<&DbScale> {
+        match *self {
+            Tlv::Db(ref d) => Some(d),
+            Tlv::Container(ref c) => c.iter().filter_map(|t| t.get_db_scale()).next(),
+            _ => None,
+        }
+    }
+
+    fn push_raw(&self, v: &mut Vec<u32>) {
+        match *self {
+            Tlv::Container(ref c) => {
+                let start = push_header(v, SND_CTL_TLVT_CONTAINER);
+                for t in c { t.push_raw(v) }
+                set_length(v, start);
+            },
+            Tlv::Db(ref d) => d.to_raw(v),
+            Tlv::Chmap(t, ref p) => {
+                let start = push_header(v, match t {
+                    ChmapType::Var => SND_CTL_TLVT_CHMAP_VAR,
+                    ChmapType::Paired => SND_CTL_TLVT_CHMAP_PAIRED,
+                    _ => SND_CTL_TLVT_CHMAP_FIXED,
+                });
+                v.extend(p.iter().map(|&x| x as u32));
+                set_length(v, start);
+            },
+            Tlv::Unknown(t, ref d) => {
+                let start = push_header(v, t);
+                v.extend_from_slice(d);
+                set_length(v, start);
+            },
+        }
+    }
+}
+
+fn parse_db(t: u32, d: &[u32]) -> Result<Option<DbScale>> {
+    let minmax = |d: &[u32]| -> Result<(MilliBel, MilliBel)> {
+        if d.len() < 2 { Err(invalid("snd_tlv_parse_dB_info")) }
+        else { Ok((MilliBel(d[0] as i32 as i64), MilliBel(d[1] as i32 as i64))) }
+    };
+    Ok(Some(match t {
+        SND_CTL_TLVT_DB_SCALE => {
+            let (min, s) = try!(minmax(d));
+            DbScale::Scale { min: min, step: MilliBel(s.0 & 0xffff), mute: (s.0 as u32 & SND_CTL_TLV_DB_SCALE_MUTE) != 0 }
+        },
+        SND_CTL_TLVT_DB_LINEAR => { let (min, max) = try!(minmax(d)); DbScale::Linear { min: min, max: max } },
+        SND_CTL_TLVT_DB_MINMAX => { let (min, max) = try!(minmax(d)); DbScale::MinMax { min: min, max: max } },
+        SND_CTL_TLVT_DB_MINMAX_MUTE => { let (min, max) = try!(minmax(d)); DbScale::MinMaxMute { min: min, max: max } },
+        SND_CTL_TLVT_DB_RANGE => {
+            let mut items = vec!();
+            let mut d = d;
+            while d.len() > 0 {
+                if d.len() < 2 { return Err(invalid("snd_tlv_parse_dB_info")) }
+                let (sub, used) = try!(parse(&d[2..]));
+                let scale = match sub { Tlv::Db(s) => s, _ => return Err(invalid("snd_tlv_parse_dB_info")) };
+                items.push(DbRangeItem { min: d[0] as i32 as i64, max: d[1] as i32 as i64, scale: scale });
+                d = &d[2+used..];
+            }
+            DbScale::Range(items)
+        },
+        _ => return Ok(None),
+    }))
+}
+
+fn parse_chmap(t: ChmapType, d: &[u32]) -> Option<Tlv> {
+    let mut r = vec!();
+    for &x in d {
+        match ChmapPosition::all().iter().find(|&&p| p as u32 == x) {
+            Some(&p) => r.push(p),
+            None => return None,
+        }
+    }
+    Some(Tlv::Chmap(t, r))
+}
+
+/// Returns the decoded TLV and the number of u32s it used
+fn parse(v: &[u32]) -> Result<(Tlv, usize)> {
+    if v.len() < 2 { return Err(invalid("snd_tlv_parse")) }
+    let t = v[0];
+    let len = (v[1] as usize + 3) / 4;
+    if v.len() < 2 + len { return Err(invalid("snd_tlv_parse")) }
+    let d = &v[2..2+len];
+    let r = match t {
+        SND_CTL_TLVT_CONTAINER => {
+            let mut c = vec!();
+            let mut d = d;
+            while d.len() > 0 {
+                let (sub, used) = try!(parse(d));
+                c.push(sub);
+                d = &d[used..];
+            }
+            Some(Tlv::Container(c))
+        },
+        SND_CTL_TLVT_CHMAP_FIXED => parse_chmap(ChmapType::Fixed, d),
+        SND_CTL_TLVT_CHMAP_VAR => parse_chmap(ChmapType::Var, d),
+        SND_CTL_TLVT_CHMAP_PAIRED => parse_chmap(ChmapType::Paired, d),
+        _ => try!(parse_db(t, d)).map(Tlv::Db),
+    }.unwrap_or_else(|| Tlv::Unknown(t, d.into()));
+    Ok((r, 2 + len))
+}
+
+#[test]
+fn tlv_db_scale() {
+    // Captured from an HDA Intel "Front Playback Volume" (range 0..29)
+    let raw = [1, 8, 0xffffef02, 0x10096];
+    let t = Tlv::from_raw(&raw).unwrap();
+    let s = DbScale::Scale { min: MilliBel(-4350), step: MilliBel(150), mute: true };
+    assert_eq!(t, Tlv::Db(s.clone()));
+    assert_eq!(&t.to_raw()[..], &raw[..]);
+    assert_eq!(s.to_db(0, 0, 29).unwrap(), DB_GAIN_MUTE);
+    assert_eq!(s.to_db(29, 0, 29).unwrap(), MilliBel(0));
+    assert_eq!(s.to_db(14, 0, 29).unwrap(), MilliBel(-2250));
+    assert_eq!(s.get_db_range(0, 29).unwrap(), (DB_GAIN_MUTE, MilliBel(0)));
+    assert_eq!(s.from_db(MilliBel(-2250), 0, 29, Round::Floor).unwrap(), 14);
+    assert_eq!(s.from_db(MilliBel(-2251), 0, 29, Round::Floor).unwrap(), 13);
+    assert_eq!(s.from_db(MilliBel(-2251), 0, 29, Round::Ceil).unwrap(), 14);
+}
+
+#[test]
+fn tlv_db_range() {
+    // Captured from a USB audio "PCM Playback Volume"-like control with two ranges (0..3 and 4..40)
+    let raw = [3, 48, 0, 3, 1, 8, 0xffffe0c0, 0x3e8, 4, 40, 4, 8, 0xfffff448, 0];
+    let t = Tlv::from_raw(&raw).unwrap();
+    let s = t.get_db_scale().unwrap().clone();
+    assert_eq!(s, DbScale::Range(vec!(
+        DbRangeItem { min: 0, max: 3, scale: DbScale::Scale { min: MilliBel(-8000), step: MilliBel(1000), mute: false }},
+        DbRangeItem { min: 4, max: 40, scale: DbScale::MinMax { min: MilliBel(-3000), max: MilliBel(0) }},
+    )));
+    assert_eq!(&t.to_raw()[..], &raw[..]);
+    assert_eq!(s.get_db_range(0, 40).unwrap(), (MilliBel(-8000), MilliBel(0)));
+    assert_eq!(s.to_db(2, 0, 40).unwrap(), MilliBel(-6000));
+    assert_eq!(s.to_db(22, 0, 40).unwrap(), MilliBel(-1500));
+    assert_eq!(s.from_db(MilliBel(-1500), 0, 40, Round::Floor).unwrap(), 22);
+    assert_eq!(s.from_db(MilliBel(-5500), 0, 40, Round::Floor).unwrap(), 2);
+    assert!(s.to_db(41, 0, 40).is_err());
+
+    // The TLV goes past the element's range of 0..20
+    let s = DbScale::Range(vec!(
+        DbRangeItem { min: 0, max: 3, scale: DbScale::Scale { min: MilliBel(-8000), step: MilliBel(1000), mute: false }},
+        DbRangeItem { min: 4, max: 40, scale: DbScale::Scale { min: MilliBel(-4000), step: MilliBel(100), mute: false }},
+    ));
+    assert_eq!(s.get_db_range(0, 20).unwrap(), (MilliBel(-8000), MilliBel(-2400)));
+    assert_eq!(s.from_db(MilliBel(-400), 0, 20, Round::Floor).unwrap(), 20);
+    assert_eq!(s.from_db(MilliBel(-3000), 0, 20, Round::Floor).unwrap(), 14);
+}
+
+#[test]
+fn tlv_db_linear() {
+    let s = DbScale::Linear { min: DB_GAIN_MUTE, max: MilliBel(0) };
+    assert_eq!(s.to_db(0, 0, 100).unwrap(), DB_GAIN_MUTE);
+    assert_eq!(s.to_db(100, 0, 100).unwrap(), MilliBel(0));
+    assert_eq!(s.to_db(50, 0, 100).unwrap(), MilliBel(-602));
+    assert_eq!(s.from_db(MilliBel(-602), 0, 100, Round::Floor).unwrap(), 50);
+}
+
+#[test]
+fn tlv_container_chmap() {
+    let raw = [0, 32, 0x101, 8, 3, 4, 4, 8, 0xfffff830, 0];
+    let t = Tlv::from_raw(&raw).unwrap();
+    assert_eq!(t, Tlv::Container(vec!(
+        Tlv::Chmap(ChmapType::Fixed, vec!(ChmapPosition::FL, ChmapPosition::FR)),
+        Tlv::Db(DbScale::MinMax { min: MilliBel(-2000), max: MilliBel(0) }),
+    )));
+    assert_eq!(&t.to_raw()[..], &raw[..]);
+    assert_eq!(Tlv::from_raw(&[0x101, 4, 0x10003]).unwrap(), Tlv::Unknown(0x101, vec!(0x10003)));
+    assert!(Tlv::from_raw(&[1, 8, 0]).is_err());
+}
@@ -0,0 +1,264 @@
+//! Use Case Manager (UCM) - set up verbs, devices and modifiers of a card
+//!
+//! Many modern SoC and laptop cards need their mixer set up by UCM before any sound comes out.
+//!
+//! # Example
+//! List verbs and devices of the first card, and which PCM to use for each device.
+//!
+//! ```no_run
+//! use alsa::ucm::{Manager, PLAYBACK_PCM};
+//! let m = Manager::from_card(&alsa::Card::new(0)).unwrap();
+//! for v in m.verbs().unwrap() {
+//!     println!("{} ({:?})", v.name, v.comment);
+//!     m.set_verb(&v.name).unwrap();
+//!     for d in m.devices(None).unwrap() {
+//!         println!("  {}: {:?}", d.name, m.get_value(PLAYBACK_PCM, Some(&d.name), None).unwrap());
+//!     }
+//! }
+//! ```
+
+use libc::{c_char, c_int, c_long};
+use std::ffi::{CStr, CString};
+use std::ptr;
+use super::error::*;
+use super::Card;
+
+// Not in alsa-sys
+#[allow(non_camel_case_types)]
+enum snd_use_case_mgr_t {}
+
+extern "C" {
+    fn snd_use_case_mgr_open(uc_mgr: *mut *mut snd_use_case_mgr_t, card_name: *const c_char) -> c_int;
+    fn snd_use_case_mgr_reload(uc_mgr: *mut snd_use_case_mgr_t) -> c_int;
+    fn snd_use_case_mgr_reset(uc_mgr: *mut snd_use_case_mgr_t) -> c_int;
+    fn snd_use_case_mgr_close(uc_mgr: *mut snd_use_case_mgr_t) -> c_int;
+    fn snd_use_case_get_list(uc_mgr: *mut snd_use_case_mgr_t, identifier: *const c_char, list: *mut *mut *const c_char) -> c_int;
+    fn snd_use_case_free_list(list: *mut *const c_char, items: c_int) -> c_int;
+    fn snd_use_case_get(uc_mgr: *mut snd_use_case_mgr_t, identifier: *const c_char, value: *mut *const c_char) -> c_int;
+    fn snd_use_case_geti(uc_mgr: *mut snd_use_case_mgr_t, identifier: *const c_char, value: *mut c_long) -> c_int;
+    fn snd_use_case_set(uc_mgr: *mut snd_use_case_mgr_t, identifier: *const c_char, value: *const c_char) -> c_int;
+}
+
+/// Value name of the PCM device to use for playback, e g, "hw:0,0"
+pub const PLAYBACK_PCM: &'static str = "PlaybackPCM";
+/// Value name of the PCM device to use for capture
+pub const CAPTURE_PCM: &'static str = "CapturePCM";
+/// Value name of the simple mixer element to use for playback volume
+pub const PLAYBACK_MIXER_ELEM: &'static str = "PlaybackMixerElem";
+/// Value name of the simple mixer element to use for capture volume
+pub const CAPTURE_MIXER_ELEM: &'static str = "CaptureMixerElem";
+/// Value name of the jack detection control for a device
+pub const JACK_CONTROL: &'static str = "JackControl";
+
+/// A verb, device or modifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    pub comment: Option<String>,
+}
+
+/// [snd_use_case_mgr_t](http://www.alsa-project.org/alsa-doc/alsa-lib/group__ucm.html) wrapper
+pub struct Manager(*mut snd_use_case_mgr_t);
+
+unsafe impl Send for Manager {}
+
+impl Drop for Manager {
+    fn drop(&mut self) { unsafe { snd_use_case_mgr_close(self.0) }; }
+}
+
+fn is_enoent(e: &Error) -> bool { e.kind() == ErrorKind::NotFound }
+
+impl Manager {
+    /// Opens the manager for a card name, e g, "hw:0" or the card's UCM configuration name.
+    pub fn open(card_name: &str) -> Result<Manager> {
+        let mut p = ptr::null_mut();
+        let c = CString::new(card_name).unwrap();
+        from_code("snd_use_case_mgr_open", unsafe { snd_use_case_mgr_open(&mut p, c.as_ptr()) })
+            .map(|_| Manager(p))
+    }
+
+    pub fn from_card(c: &Card) -> Result<Manager> { Manager::open(&format!("hw:{}", c.get_index())) }
+
+    pub fn reload(&self) -> Result<()> {
+        from_code("snd_use_case_mgr_reload", unsafe { snd_use_case_mgr_reload(self.0) }).map(|_| ())
+    }
+
+    /// Resets the manager: no verb, devices or modifiers are set.
+    pub fn reset(&self) -> Result<()> {
+        from_code("snd_use_case_mgr_reset", unsafe { snd_use_case_mgr_reset(self.0) }).map(|_| ())
+    }
+
+    /// Raw wrapper around snd_use_case_get_list; entries can be null.
+    pub fn get_list(&self, identifier: &str) -> Result<Vec<Option<String>>> {
+        let mut l = ptr::null_mut();
+        let id = CString::new(identifier).unwrap();
+        let n = try!(from_code("snd_use_case_get_list", unsafe { snd_use_case_get_list(self.0, id.as_ptr(), &mut l) }));
+        let mut r = vec!();
+        for i in 0..n as isize {
+            let p = unsafe { *l.offset(i) };
+            r.push(if p.is_null() { None } else { Some(unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()) });
+        }
+        if n > 0 { unsafe { snd_use_case_free_list(l, n) }; }
+        Ok(r)
+    }
+
+    fn get_items(&self, identifier: &str) -> Result<Vec<Item>> {
+        let l = try!(self.get_list(identifier));
+        Ok(l.chunks(2).filter_map(|c| c[0].clone().map(|n|
+            Item { name: n, comment: c.get(1).and_then(|x| x.clone()) })).collect())
+    }
+
+    fn get_names(&self, identifier: &str) -> Result<Vec<String>> {
+        self.get_list(identifier).map(|l| l.into_iter().filter_map(|x| x).collect())
+    }
+
+    fn with_verb(identifier: &str, verb: Option<&str>) -> String {
+        match verb { Some(v) => format!("{}/{}", identifier, v), None => identifier.into() }
+    }
+
+    pub fn verbs(&self) -> Result<Vec<Item>> { self.get_items("_verbs") }
+
+    /// Devices of a verb, or of the current verb if `None`.
+    pub fn devices(&self, verb: Option<&str>) -> Result<Vec<Item>> { self.get_items(&Manager::with_verb("_devices", verb)) }
+
+    /// Modifiers of a verb, or of the current verb if `None`.
+    pub fn modifiers(&self, verb: Option<&str>) -> Result<Vec<Item>> { self.get_items(&Manager::with_verb("_modifiers", verb)) }
+
+    /// Raw wrapper around snd_use_case_get.
+    pub fn get(&self, identifier: &str) -> Result<String> {
+        let mut v = ptr::null();
+        let id = CString::new(identifier).unwrap();
+        try!(from_code("snd_use_case_get", unsafe { snd_use_case_get(self.0, id.as_ptr(), &mut v) }));
+        from_alloc("snd_use_case_get", v as *mut c_char)
+    }
+
+    /// Raw wrapper around snd_use_case_geti.
+    pub fn geti(&self, identifier: &str) -> Result<i64> {
+        let mut v = 0;
+        let id = CString::new(identifier).unwrap();
+        from_code("snd_use_case_geti", unsafe { snd_use_case_geti(self.0, id.as_ptr(), &mut v) }).map(|_| v as i64)
+    }
+
+    /// Raw wrapper around snd_use_case_set.
+    pub fn set(&self, identifier: &str, value: &str) -> Result<()> {
+        let id = CString::new(identifier).unwrap();
+        let v = CString::new(value).unwrap();
+        from_code("snd_use_case_set", unsafe { snd_use_case_set(self.0, id.as_ptr(), v.as_ptr()) }).map(|_| ())
+    }
+
+    /// Returns the current verb, or `None` if no verb is set.
+    pub fn get_verb(&self) -> Result<Option<String>> {
+        match self.get("_verb") {
+            Err(ref e) if is_enoent(e) => Ok(None),
+            r => r.map(Some),
+        }
+    }
+
+    pub fn set_verb(&self, verb: &str) -> Result<()> { self.set("_verb", verb) }
+
+    pub fn enabled_devices(&self) -> Result<Vec<String>> { self.get_names("_enadevs") }
+    pub fn enable_device(&self, device: &str) -> Result<()> { self.set("_enadev", device) }
+    pub fn disable_device(&self, device: &str) -> Result<()> { self.set("_disdev", device) }
+    /// Disables `old` and enables `new` in one go
+    pub fn switch_device(&self, old: &str, new: &str) -> Result<()> { self.set(&format!("_swdev/{}", old), new) }
+    pub fn device_status(&self, device: &str) -> Result<bool> { self.geti(&format!("_devstatus/{}", device)).map(|x| x != 0) }
+
+    pub fn enabled_modifiers(&self) -> Result<Vec<String>> { self.get_names("_enamods") }
+    pub fn enable_modifier(&self, modifier: &str) -> Result<()> { self.set("_enamod", modifier) }
+    pub fn disable_modifier(&self, modifier: &str) -> Result<()> { self.set("_dismod", modifier) }
+    pub fn modifier_status(&self, modifier: &str) -> Result<bool> { self.geti(&format!("_modstatus/{}", modifier)).map(|x| x != 0) }
+
+    /// Returns a value, e g, `PLAYBACK_PCM`, for a device or modifier and a verb (or the current verb, if `None`).
+    ///
+    /// Returns `None` if the value is not set. A verb can only be given together with a device or modifier.
+    pub fn get_value(&self, name: &str, device_or_modifier: Option<&str>, verb: Option<&str>) -> Result<Option<String>> {
+        let id = match (device_or_modifier, verb) {
+            (None, None) => name.to_string(),
+            (Some(d), None) => format!("{}/{}", name, d),
+            (Some(d), Some(v)) => format!("{}/{}/{}", name, d, v),
+            (None, Some(_)) => return Err(Error::new("snd_use_case_get", ::libc::EINVAL)),
+        };
+        match self.get(&id) {
+            Err(ref e) if is_enoent(e) => Ok(None),
+            r => r.map(Some),
+        }
+    }
+}
+
+/// Points alsa-lib to another UCM configuration directory while `f` runs.
+///
+/// The environment is process wide, so this is serialized and the old value is restored afterwards.
+#[cfg(test)]
+fn with_ucm_dir<F: FnOnce()>(dir: &::std::path::Path, f: F) {
+    use std::sync::{Mutex, Once, ONCE_INIT};
+    use std::{env, panic};
+    static INIT: Once = ONCE_INIT;
+    static mut LOCK: *const Mutex<()> = 0 as *const _;
+    let _g = unsafe {
+        INIT.call_once(|| LOCK = Box::into_raw(Box::new(Mutex::new(()))));
+        (*LOCK).lock().unwrap_or_else(|e| e.into_inner())
+    };
+    let old = env::var_os("ALSA_CONFIG_UCM2");
+    env::set_var("ALSA_CONFIG_UCM2", dir);
+    let r = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    match old {
+        Some(o) => env::set_var("ALSA_CONFIG_UCM2", o),
+        None => env::remove_var("ALSA_CONFIG_UCM2"),
+    }
+    if let Err(e) = r { panic::resume_unwind(e) }
+}
+
+#[test]
+fn ucm_config() {
+    use std::{env, fs};
+    use std::io::Write;
+    let dir = env::temp_dir().join(format!("alsa-rs-ucm-{}", ::std::process::id()));
+    let card = dir.join("RustTest");
+    fs::create_dir_all(&card).unwrap();
+    fs::File::create(card.join("RustTest.conf")).unwrap().write_all(br#"
+Syntax 2
+SectionUseCase."HiFi" {
+    File "HiFi.conf"
+    Comment "Play HiFi quality music"
+}
+"#).unwrap();
+    fs::File::create(card.join("HiFi.conf")).unwrap().write_all(br#"
+SectionVerb {
+    EnableSequence [ ]
+    DisableSequence [ ]
+}
+SectionDevice."Speaker" {
+    Comment "Speaker"
+    Value {
+        PlaybackPCM "hw:RustTest,0"
+        PlaybackMixerElem "Speaker"
+    }
+}
+SectionDevice."Headphones" {
+    Comment "Headphones"
+    Value {
+        PlaybackPCM "hw:RustTest,0"
+        JackControl "Headphone Jack"
+    }
+}
+"#).unwrap();
+    with_ucm_dir(&dir, || {
+        let m = Manager::open("RustTest").unwrap();
+        assert_eq!(m.verbs().unwrap(), vec!(Item { name: "HiFi".into(), comment: Some("Play HiFi quality music".into()) }));
+        assert_eq!(m.get_verb().unwrap(), None);
+        m.set_verb("HiFi").unwrap();
+        assert_eq!(m.get_verb().unwrap(), Some("HiFi".into()));
+        let d: Vec<String> = m.devices(None).unwrap().into_iter().map(|x| x.name).collect();
+        assert_eq!(d, vec!("Speaker".to_string(), "Headphones".to_string()));
+        assert_eq!(m.get_value(PLAYBACK_PCM, Some("Speaker"), None).unwrap(), Some("hw:RustTest,0".into()));
+        assert_eq!(m.get_value(PLAYBACK_MIXER_ELEM, Some("Speaker"), Some("HiFi")).unwrap(), Some("Speaker".into()));
+        assert_eq!(m.get_value(JACK_CONTROL, Some("Headphones"), None).unwrap(), Some("Headphone Jack".into()));
+        assert_eq!(m.get_value(JACK_CONTROL, Some("Speaker"), None).unwrap(), None);
+        assert!(m.get_value(PLAYBACK_PCM, None, Some("HiFi")).is_err());
+        m.enable_device("Headphones").unwrap();
+        assert_eq!(m.enabled_devices().unwrap(), vec!("Headphones".to_string()));
+        assert!(m.device_status("Headphones").unwrap());
+        drop(m);
+    });
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -23,10 +23,13 @@ fn connect_midi_source_ports(s: &alsa::Seq, our_port: i32) -> Result<(), Box<err
             if !caps.contains(seq::READ) || !caps.contains(seq::SUBS_READ) { continue; }
             if !port.get_type().contains(seq::MIDI_GENERIC) { continue; }
 
-            // Connect source and dest ports
+            // Connect source and dest ports, unless they are connected already
+            let sender = seq::Addr { client: port.get_client(), port: port.get_port() };
+            let dest = seq::Addr { client: our_id, port: our_port };
+            if s.get_port_subscription(sender, dest)?.is_some() { continue; }
             let subs = seq::PortSubscribe::empty()?;
-            subs.set_sender(seq::Addr { client: port.get_client(), port: port.get_port() });
-            subs.set_dest(seq::Addr { client: our_id, port: our_port });
+            subs.set_sender(sender);
+            subs.set_dest(dest);
             println!("Reading from midi input {:?}", port);
             s.subscribe_port(&subs)?;
         }